@@ -47,8 +47,10 @@ fn main() {
             // Print details about failed files
             if !result.errors.is_empty() {
                 info!("\nFiles that failed to process:");
-                for (path, error) in result.errors {
-                    error!("  {}: {}", path.display(), error);
+                for (path, errors) in result.errors {
+                    for error in errors {
+                        error!("  {}: {}", path.display(), error);
+                    }
                 }
             }
             
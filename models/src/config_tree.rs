@@ -0,0 +1,725 @@
+//! Merged, hierarchical view of all scanned classes
+//!
+//! Parsers return classes per file. Arma merges every addon's config into one
+//! global tree where a class is identified by its full path (`CfgWeapons/arifle_MX_F/ItemInfo`)
+//! rather than its bare name, later definitions update earlier ones and `parent`
+//! names are looked up relative to the scope the class is declared in.
+//! [`ConfigTree`] models that tree.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+
+use crate::{ClassKind, ClassProperty, GameClass, PropertyValue, SourceSpan};
+
+/// Separator between class names in a node path
+pub const PATH_SEPARATOR: char = '/';
+
+/// Index of a node within a [`ConfigTree`]
+pub type NodeId = usize;
+
+/// A single class in the merged tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigNode {
+    /// Class name, as spelled in the first definition
+    pub name: String,
+
+    /// Full path of the class, e.g. `CfgWeapons/arifle_MX_F/ItemInfo`
+    pub path: String,
+
+    /// Parent class name as written in the source (if any)
+    pub parent_name: Option<String>,
+
+    /// Resolved parent node, set by [`ConfigTree::resolve`]
+    pub parent: Option<NodeId>,
+
+    /// Class this node is nested in
+    pub container: Option<NodeId>,
+
+    /// Nested classes, in definition order
+    pub children: Vec<NodeId>,
+
+    /// Non-class properties after merging all definitions
+    pub properties: Vec<ClassProperty>,
+
+    /// Files that define or update this class, in merge order
+    pub sources: Vec<PathBuf>,
+
+    /// Location of the first definition (if known)
+    pub span: Option<SourceSpan>,
+
+    /// `External` while the class has only been declared with `class Foo;`,
+    /// `Deleted` once a `delete` statement removed it from the tree
+    pub kind: ClassKind,
+}
+
+impl ConfigNode {
+    /// Find a property defined directly on this class (case-insensitive)
+    pub fn property(&self, name: &str) -> Option<&ClassProperty> {
+        self.properties.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Number of containers above this node
+    pub fn depth(&self) -> usize {
+        self.path.matches(PATH_SEPARATOR).count()
+    }
+}
+
+/// A property value resolved through inheritance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveProperty {
+    /// Property name, as spelled where it was defined
+    pub name: String,
+
+    /// Property value
+    pub value: PropertyValue,
+
+    /// Path of the nearest class that sets or extends the value
+    pub source: String,
+
+    /// Where the value was defined (if known)
+    pub span: Option<SourceSpan>,
+}
+
+impl EffectiveProperty {
+    fn new(property: &ClassProperty, node: &ConfigNode) -> Self {
+        Self {
+            name: property.name.clone(),
+            value: property.value.clone(),
+            source: node.path.clone(),
+            span: property.span.clone(),
+        }
+    }
+
+    /// Apply a declaration on top of the value inherited so far
+    ///
+    /// `+=` extends an inherited array; anything else replaces the value.
+    fn apply(inherited: Option<Self>, property: &ClassProperty, node: &ConfigNode) -> Self {
+        let mut effective = Self::new(property, node);
+        if property.is_append() {
+            if let (Some(PropertyValue::Array(mut items)), PropertyValue::Array(appended)) =
+                (inherited.map(|p| p.value), &property.value)
+            {
+                items.extend(appended.iter().cloned());
+                effective.value = PropertyValue::Array(items);
+            }
+        }
+        effective
+    }
+
+    /// Whether the value is defined by the class itself rather than inherited
+    pub fn is_local_to(&self, class_path: &str) -> bool {
+        self.source.eq_ignore_ascii_case(class_path.trim_matches(PATH_SEPARATOR))
+    }
+}
+
+/// Problems found while building or resolving a [`ConfigTree`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfigTreeError {
+    /// The parent class could not be found in any visible scope
+    UnresolvedParent {
+        /// Path of the class declaring the parent
+        class_path: String,
+        /// Parent name as written in the source
+        parent: String,
+    },
+
+    /// Following the parent chain leads back to the class itself
+    CircularInheritance {
+        /// Path of the class whose parent link closes the cycle
+        class_path: String,
+        /// Parent name as written in the source
+        parent: String,
+    },
+}
+
+impl fmt::Display for ConfigTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigTreeError::UnresolvedParent { class_path, parent } => {
+                write!(f, "Unresolved parent class '{}' for '{}'", parent, class_path)
+            }
+            ConfigTreeError::CircularInheritance { class_path, parent } => {
+                write!(f, "Circular inheritance: '{}' inherits from '{}'", class_path, parent)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigTreeError {}
+
+/// All classes merged into a single tree keyed by full path
+///
+/// Lookups are case-insensitive, like the game's. Classes are added with
+/// [`ConfigTree::add_class`] in load order, then [`ConfigTree::resolve`] links
+/// every `parent` name to a node.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigTree {
+    nodes: Vec<ConfigNode>,
+    /// Lowercase full path -> node
+    index: HashMap<String, NodeId>,
+    roots: Vec<NodeId>,
+    errors: Vec<ConfigTreeError>,
+}
+
+impl ConfigTree {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a resolved tree from parsed classes
+    ///
+    /// Only top-level classes (`container_class` is `None`) are taken; nested
+    /// classes are reached through their container's `PropertyValue::Class`
+    /// properties. Classes are merged in iteration order, so pass them in load order.
+    pub fn from_classes<'a, I>(classes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a GameClass>,
+    {
+        let mut tree = Self::new();
+        for class in classes {
+            if class.container_class.is_none() {
+                tree.add_class(class);
+            }
+        }
+        tree.resolve();
+        tree
+    }
+
+    /// Merge a top-level class (and everything nested in it) into the tree
+    ///
+    /// Redefining an existing class follows the game's merge rules: properties
+    /// with the same name are overwritten (or extended, for `+=` arrays), new
+    /// properties and classes are added and nested classes are merged
+    /// recursively. A redefinition sets the parent as written, so one without a
+    /// parent clears it, like the game does. External declarations only create
+    /// the class if it doesn't exist yet and never change its parent,
+    /// and deletions remove it with everything nested in it. Call
+    /// [`ConfigTree::resolve`] afterwards.
+    ///
+    /// Returns the node of the class, or `None` if it was deleted.
+    pub fn add_class(&mut self, class: &GameClass) -> Option<NodeId> {
+        self.merge_class(class, None)
+    }
+
+    fn merge_class(&mut self, class: &GameClass, container: Option<NodeId>) -> Option<NodeId> {
+        let path = match container {
+            Some(id) => format!("{}{}{}", self.nodes[id].path, PATH_SEPARATOR, class.name),
+            None => class.name.clone(),
+        };
+        let existing = self.index.get(&path.to_lowercase()).copied();
+
+        let id = match (class.kind, existing) {
+            (ClassKind::Deleted, Some(id)) => {
+                self.delete_node(id);
+                return None;
+            }
+            (ClassKind::Deleted, None) => return None,
+            (ClassKind::External, Some(id)) => return Some(id),
+            (ClassKind::External, None) => {
+                return Some(self.insert_node(&class.name, path, container, class.span.clone(), ClassKind::External));
+            }
+            (ClassKind::Definition, Some(id)) => id,
+            (ClassKind::Definition, None) => {
+                self.insert_node(&class.name, path, container, class.span.clone(), ClassKind::Definition)
+            }
+        };
+
+        {
+            let node = &mut self.nodes[id];
+            if node.kind == ClassKind::External {
+                node.kind = ClassKind::Definition;
+                node.span = class.span.clone();
+            }
+            node.parent_name = class.parent.clone();
+            if !node.sources.contains(&class.file_path) {
+                node.sources.push(class.file_path.clone());
+            }
+        }
+
+        for property in &class.properties {
+            match &property.value {
+                PropertyValue::Class(nested) => {
+                    self.merge_class(nested, Some(id));
+                }
+                _ => {
+                    let properties = &mut self.nodes[id].properties;
+                    match properties.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&property.name)) {
+                        // `+=` in a redefinition extends the array of the same class
+                        Some(existing) if property.is_append() => {
+                            if let (PropertyValue::Array(items), PropertyValue::Array(appended)) =
+                                (&mut existing.value, &property.value)
+                            {
+                                items.extend(appended.iter().cloned());
+                            } else {
+                                existing.value = property.value.clone();
+                            }
+                            existing.span = property.span.clone();
+                        }
+                        Some(existing) => *existing = property.clone(),
+                        None => properties.push(property.clone()),
+                    }
+                }
+            }
+        }
+
+        Some(id)
+    }
+
+    fn insert_node(
+        &mut self,
+        name: &str,
+        path: String,
+        container: Option<NodeId>,
+        span: Option<SourceSpan>,
+        kind: ClassKind,
+    ) -> NodeId {
+        let id = self.nodes.len();
+        self.index.insert(path.to_lowercase(), id);
+        self.nodes.push(ConfigNode {
+            name: name.to_string(),
+            path,
+            parent_name: None,
+            parent: None,
+            container,
+            children: Vec::new(),
+            properties: Vec::new(),
+            sources: Vec::new(),
+            span,
+            kind,
+        });
+
+        match container {
+            Some(container) => self.nodes[container].children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    /// Detach a node from the tree and mark it and everything nested in it as deleted
+    ///
+    /// The nodes stay in the arena so ids remain valid, but they can no longer
+    /// be found by path or by walking the tree.
+    fn delete_node(&mut self, id: NodeId) {
+        match self.nodes[id].container {
+            Some(container) => self.nodes[container].children.retain(|&child| child != id),
+            None => self.roots.retain(|&root| root != id),
+        }
+
+        let mut pending = vec![id];
+        while let Some(current) = pending.pop() {
+            let node = &mut self.nodes[current];
+            node.kind = ClassKind::Deleted;
+            self.index.remove(&node.path.to_lowercase());
+            pending.append(&mut node.children);
+        }
+    }
+
+    /// Link every class to its parent node
+    ///
+    /// A parent name is looked up the way the game does it: first among the
+    /// classes of the enclosing scope, then among the classes that scope inherits
+    /// from its own parent, then in each outer scope up to the root. Containers
+    /// are resolved before the classes nested in them so inherited scopes are known.
+    /// Errors from earlier calls are discarded.
+    pub fn resolve(&mut self) {
+        self.errors.clear();
+        for node in &mut self.nodes {
+            node.parent = None;
+        }
+
+        let mut order: Vec<NodeId> = (0..self.nodes.len()).collect();
+        order.sort_by_key(|&id| self.nodes[id].depth());
+
+        for id in order {
+            if self.nodes[id].kind == ClassKind::Deleted {
+                continue;
+            }
+            let Some(parent_name) = self.nodes[id].parent_name.clone() else {
+                continue;
+            };
+
+            match self.lookup_parent(id, &parent_name) {
+                Some(parent) if self.inherits_from(parent, id) => {
+                    self.errors.push(ConfigTreeError::CircularInheritance {
+                        class_path: self.nodes[id].path.clone(),
+                        parent: parent_name,
+                    });
+                }
+                Some(parent) => self.nodes[id].parent = Some(parent),
+                None => {
+                    self.errors.push(ConfigTreeError::UnresolvedParent {
+                        class_path: self.nodes[id].path.clone(),
+                        parent: parent_name,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Find the class `name` refers to when used as parent of `id`
+    fn lookup_parent(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        let mut scope = self.nodes[id].container;
+        while let Some(current_scope) = scope {
+            if let Some(found) = self.find_named(&self.nodes[current_scope].children, name, id) {
+                return Some(found);
+            }
+
+            // Classes inherited by the scope, e.g. `class ItemInfo: ItemInfo` inside a weapon
+            for base in self.ancestors(current_scope) {
+                if let Some(found) = self.find_named(&self.nodes[base].children, name, id) {
+                    return Some(found);
+                }
+            }
+
+            scope = self.nodes[current_scope].container;
+        }
+
+        self.find_named(&self.roots, name, id)
+    }
+
+    fn find_named(&self, candidates: &[NodeId], name: &str, exclude: NodeId) -> Option<NodeId> {
+        candidates.iter()
+            .copied()
+            .find(|&c| c != exclude && self.nodes[c].name.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether `ancestor` appears in the parent chain of `id` (or is `id` itself)
+    fn inherits_from(&self, id: NodeId, ancestor: NodeId) -> bool {
+        id == ancestor || self.ancestors(id).any(|node| node == ancestor)
+    }
+
+    /// Look up a class by path, following inheritance like the game does
+    ///
+    /// Unlike [`ConfigTree::get`], a path segment that is not defined directly
+    /// is looked up in the parent chain, so `CfgWeapons/arifle_MX_F/ItemInfo`
+    /// finds the `ItemInfo` a rifle inherits from its base class.
+    pub fn effective_class(&self, path: &str) -> Option<NodeId> {
+        let mut segments = path.split(PATH_SEPARATOR).filter(|segment| !segment.is_empty());
+        let first = segments.next()?;
+        let mut current = self.roots.iter()
+            .copied()
+            .find(|&id| self.nodes[id].name.eq_ignore_ascii_case(first))?;
+
+        for segment in segments {
+            current = std::iter::once(current)
+                .chain(self.ancestors(current))
+                .find_map(|id| self.find_named(&self.nodes[id].children, segment, id))?;
+        }
+
+        Some(current)
+    }
+
+    /// Value of a property as the game sees it, including inherited values
+    ///
+    /// The class is found with [`ConfigTree::effective_class`], then the class
+    /// and its parents are searched nearest first. Arrays declared with `+=`
+    /// are appended to the value inherited from further up the chain. The
+    /// result records which class the value came from.
+    pub fn effective_property(&self, class_path: &str, name: &str) -> Option<EffectiveProperty> {
+        let id = self.effective_class(class_path)?;
+
+        // Declarations that contribute to the value, nearest first, up to the first plain assignment
+        let mut declarations = Vec::new();
+        for current in std::iter::once(id).chain(self.ancestors(id)) {
+            let node = &self.nodes[current];
+            if let Some(property) = node.property(name) {
+                declarations.push((property, node));
+                if !property.is_append() {
+                    break;
+                }
+            }
+        }
+
+        declarations.into_iter()
+            .rev()
+            .fold(None, |inherited, (property, node)| Some(EffectiveProperty::apply(inherited, property, node)))
+    }
+
+    /// All properties of a class including inherited ones
+    ///
+    /// Properties keep the order of their first definition in the parent chain,
+    /// with values taken from the nearest class that defines them and `+=`
+    /// arrays appended to the inherited value.
+    pub fn effective_properties(&self, class_path: &str) -> Vec<EffectiveProperty> {
+        let Some(id) = self.effective_class(class_path) else {
+            return Vec::new();
+        };
+
+        let mut chain: Vec<NodeId> = std::iter::once(id).chain(self.ancestors(id)).collect();
+        chain.reverse();
+
+        let mut properties: Vec<EffectiveProperty> = Vec::new();
+        for current in chain {
+            let node = &self.nodes[current];
+            for property in &node.properties {
+                match properties.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&property.name)) {
+                    Some(existing) => *existing = EffectiveProperty::apply(Some(existing.clone()), property, node),
+                    None => properties.push(EffectiveProperty::apply(None, property, node)),
+                }
+            }
+        }
+        properties
+    }
+
+    /// Problems found by the last [`ConfigTree::resolve`]
+    pub fn errors(&self) -> &[ConfigTreeError] {
+        &self.errors
+    }
+
+    /// Look up a class by full path, e.g. `CfgWeapons/arifle_MX_F` (case-insensitive)
+    pub fn get(&self, path: &str) -> Option<&ConfigNode> {
+        self.id_of(path).map(|id| &self.nodes[id])
+    }
+
+    /// Node id of a class by full path (case-insensitive)
+    pub fn id_of(&self, path: &str) -> Option<NodeId> {
+        self.index.get(&path.trim_matches(PATH_SEPARATOR).to_lowercase()).copied()
+    }
+
+    /// Node by id
+    pub fn node(&self, id: NodeId) -> &ConfigNode {
+        &self.nodes[id]
+    }
+
+    /// Resolved parent of a node
+    pub fn parent_of(&self, id: NodeId) -> Option<&ConfigNode> {
+        self.nodes[id].parent.map(|parent| &self.nodes[parent])
+    }
+
+    /// Parent chain of a node, nearest first
+    pub fn ancestors(&self, id: NodeId) -> Ancestors<'_> {
+        Ancestors {
+            tree: self,
+            next: self.nodes[id].parent,
+            visited: HashSet::from([id]),
+        }
+    }
+
+    /// Top-level classes, in definition order
+    pub fn roots(&self) -> impl Iterator<Item = &ConfigNode> {
+        self.roots.iter().map(|&id| &self.nodes[id])
+    }
+
+    /// Classes nested directly in a node
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = &ConfigNode> {
+        self.nodes[id].children.iter().map(|&child| &self.nodes[child])
+    }
+
+    /// All classes in the tree with their ids, skipping deleted ones
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &ConfigNode)> {
+        self.nodes.iter()
+            .enumerate()
+            .filter(|(_, node)| node.kind != ClassKind::Deleted)
+    }
+
+    /// The merged tree as top-level classes, e.g. to write it back out
+    ///
+    /// Each class carries its merged properties followed by its nested
+    /// classes as [`PropertyValue::Class`] properties, so unlike parser output
+    /// nested classes are not listed separately. A class is attributed to the
+    /// first file that defines it.
+    pub fn to_game_classes(&self) -> Vec<GameClass> {
+        self.roots.iter().map(|&id| self.game_class(id)).collect()
+    }
+
+    fn game_class(&self, id: NodeId) -> GameClass {
+        let node = &self.nodes[id];
+        let mut class = GameClass::with_container(
+            node.name.clone(),
+            node.parent_name.clone(),
+            node.sources.first().cloned().unwrap_or_default(),
+            node.container.map(|container| self.nodes[container].name.clone()),
+        );
+        class.span = node.span.clone();
+        class.kind = node.kind;
+        class.properties = node.properties.clone();
+
+        for &child in &node.children {
+            let nested = self.game_class(child);
+            let mut property = ClassProperty::new(nested.name.clone(), PropertyValue::Class(Box::new(nested)));
+            property.span = self.nodes[child].span.clone();
+            class.properties.push(property);
+        }
+        class
+    }
+
+    /// Number of classes in the tree, not counting deleted ones
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether the tree has no classes
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+/// Iterator over the parent chain of a node, see [`ConfigTree::ancestors`]
+pub struct Ancestors<'a> {
+    tree: &'a ConfigTree,
+    next: Option<NodeId>,
+    visited: HashSet<NodeId>,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let current = self.next?;
+        // Resolved trees are acyclic, but a deserialized one might not be
+        if !self.visited.insert(current) {
+            self.next = None;
+            return None;
+        }
+        self.next = self.tree.nodes[current].parent;
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayElement, PropertyOperator};
+    use crate::conversions::GameClassBuilder;
+
+    fn class(name: &str, parent: Option<&str>) -> GameClassBuilder {
+        let builder = GameClassBuilder::new(name).file_path("config.cpp");
+        match parent {
+            Some(parent) => builder.parent(parent),
+            None => builder,
+        }
+    }
+
+    fn declaration(name: &str, kind: ClassKind) -> GameClass {
+        class(name, None).kind(kind).build()
+    }
+
+    fn nested(class: GameClass) -> PropertyValue {
+        PropertyValue::Class(Box::new(class))
+    }
+
+    /// `name[] += {items}`
+    fn appended(name: &str, items: &[&str]) -> ClassProperty {
+        let mut property = ClassProperty::new(name.to_string(), items.iter().map(|&item| ArrayElement::from(item)).collect::<Vec<_>>().into());
+        property.operator = PropertyOperator::Append;
+        property
+    }
+
+    #[test]
+    fn test_merge_redefinitions() {
+        let base = class("CfgWeapons", None)
+            .add_property("Rifle", nested(class("Rifle", None)
+                .container_class("CfgWeapons")
+                .add_property("mass", 10i64)
+                .add_property("magazines", vec![ArrayElement::from("10Rnd")])
+                .build()))
+            .build();
+        let mut rifle = class("Rifle", None)
+            .file_path("patch.cpp")
+            .container_class("CfgWeapons")
+            .add_property("mass", 20i64)
+            .build();
+        rifle.properties.push(appended("magazines", &["30Rnd"]));
+        let patch = class("CfgWeapons", None).file_path("patch.cpp").add_property("Rifle", nested(rifle)).build();
+
+        let tree = ConfigTree::from_classes([&base, &patch]);
+        let rifle = tree.get("cfgweapons/RIFLE").unwrap();
+        assert!(matches!(rifle.property("mass").unwrap().value, PropertyValue::Number(20)));
+        assert!(matches!(&rifle.property("magazines").unwrap().value, PropertyValue::Array(items)
+            if items[..] == [ArrayElement::from("10Rnd"), ArrayElement::from("30Rnd")]));
+        assert_eq!(tree.get("CfgWeapons").unwrap().sources, vec![PathBuf::from("config.cpp"), PathBuf::from("patch.cpp")]);
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn test_redefinition_sets_parent() {
+        let mut tree = ConfigTree::new();
+        tree.add_class(&class("Bar", None).build());
+        tree.add_class(&class("Baz", None).build());
+        tree.add_class(&class("Foo", Some("Bar")).build());
+
+        // An external declaration keeps the parent
+        tree.add_class(&declaration("Foo", ClassKind::External));
+        assert_eq!(tree.get("Foo").unwrap().parent_name.as_deref(), Some("Bar"));
+
+        // A redefinition rebases the class, or clears the parent if it names none
+        tree.add_class(&class("Foo", Some("Baz")).build());
+        assert_eq!(tree.get("Foo").unwrap().parent_name.as_deref(), Some("Baz"));
+        tree.add_class(&class("Foo", None).build());
+        tree.resolve();
+        let foo = tree.get("Foo").unwrap();
+        assert_eq!(foo.parent_name, None);
+        assert_eq!(foo.parent, None);
+    }
+
+    #[test]
+    fn test_external_and_deleted_classes() {
+        let mut tree = ConfigTree::new();
+        tree.add_class(&declaration("Foo", ClassKind::External));
+        assert_eq!(tree.get("Foo").unwrap().kind, ClassKind::External);
+
+        // A definition after the declaration upgrades it
+        let id = tree.add_class(&class("Foo", None)
+            .add_property("Inner", nested(class("Inner", None).container_class("Foo").build()))
+            .build());
+        assert_eq!(id, tree.id_of("Foo"));
+        assert_eq!(tree.get("Foo").unwrap().kind, ClassKind::Definition);
+        assert_eq!(tree.len(), 2);
+
+        // Deleting removes the class with everything nested in it
+        assert_eq!(tree.add_class(&declaration("foo", ClassKind::Deleted)), None);
+        assert!(tree.get("Foo").is_none());
+        assert!(tree.get("Foo/Inner").is_none());
+        assert!(tree.is_empty());
+        assert_eq!(tree.roots().count(), 0);
+    }
+
+    #[test]
+    fn test_resolve_scopes() {
+        let item_info = |parent: Option<&str>, container: &str| nested(class("ItemInfo", parent).container_class(container).build());
+        let tree = ConfigTree::from_classes([
+            &class("ItemCore", None).add_property("ItemInfo", item_info(None, "ItemCore")).build(),
+            &class("Rifle", Some("ItemCore")).add_property("ItemInfo", item_info(Some("ItemInfo"), "Rifle")).build(),
+            &class("Loop_A", Some("Loop_B")).build(),
+            &class("Loop_B", Some("Loop_A")).build(),
+            &class("Orphan", Some("Missing")).build(),
+        ]);
+
+        // `class ItemInfo: ItemInfo` finds the ItemInfo inherited from ItemCore
+        let rifle_info = tree.id_of("Rifle/ItemInfo").unwrap();
+        assert_eq!(tree.parent_of(rifle_info).unwrap().path, "ItemCore/ItemInfo");
+        assert_eq!(tree.parent_of(tree.id_of("Rifle").unwrap()).unwrap().path, "ItemCore");
+
+        assert_eq!(tree.errors(), [
+            ConfigTreeError::CircularInheritance { class_path: "Loop_B".to_string(), parent: "Loop_A".to_string() },
+            ConfigTreeError::UnresolvedParent { class_path: "Orphan".to_string(), parent: "Missing".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_effective_properties() {
+        let mut rifle = class("Rifle", Some("Base")).add_property("mass", 20i64).build();
+        rifle.properties.push(appended("magazines", &["30Rnd"]));
+        let tree = ConfigTree::from_classes([
+            &class("Base", None)
+                .add_property("mass", 10i64)
+                .add_property("magazines", vec![ArrayElement::from("10Rnd")])
+                .add_property("scope", 2i64)
+                .build(),
+            &rifle,
+        ]);
+
+        let scope = tree.effective_property("Rifle", "SCOPE").unwrap();
+        assert!(!scope.is_local_to("Rifle"));
+        assert_eq!(scope.source, "Base");
+
+        let properties = tree.effective_properties("Rifle");
+        let sources: Vec<&str> = properties.iter().map(|p| p.source.as_str()).collect();
+        assert_eq!(sources, vec!["Rifle", "Rifle", "Base"]);
+        assert!(matches!(tree.effective_property("Rifle", "magazines").unwrap().value, PropertyValue::Array(items)
+            if items[..] == [ArrayElement::from("10Rnd"), ArrayElement::from("30Rnd")]));
+    }
+}
@@ -1,5 +1,5 @@
 use std::path::PathBuf;
-use crate::{GameClass, PropertyValue, ClassProperty};
+use crate::{GameClass, PropertyValue, ClassProperty, ArrayElement, SourceSpan, ClassKind, Fidelity};
 
 // Implement conversions from strings to PropertyValue
 impl From<String> for PropertyValue {
@@ -27,22 +27,86 @@ impl From<i32> for PropertyValue {
     }
 }
 
+impl From<f64> for PropertyValue {
+    fn from(value: f64) -> Self {
+        PropertyValue::Float(value)
+    }
+}
+
+impl From<f32> for PropertyValue {
+    fn from(value: f32) -> Self {
+        // Go through the shortest decimal representation so `0.35f32` becomes
+        // `0.35` rather than `0.3499999940395355`
+        PropertyValue::Float(value.to_string().parse().unwrap_or(value as f64))
+    }
+}
+
 // Implement conversions from arrays to PropertyValue
+impl From<Vec<ArrayElement>> for PropertyValue {
+    fn from(value: Vec<ArrayElement>) -> Self {
+        PropertyValue::Array(value)
+    }
+}
+
 impl From<Vec<String>> for PropertyValue {
     fn from(value: Vec<String>) -> Self {
-        PropertyValue::Array(value)
+        PropertyValue::Array(value.into_iter().map(ArrayElement::String).collect())
     }
 }
 
 impl<'a> From<&'a [String]> for PropertyValue {
     fn from(value: &'a [String]) -> Self {
-        PropertyValue::Array(value.to_vec())
+        PropertyValue::Array(value.iter().cloned().map(ArrayElement::String).collect())
     }
 }
 
 impl<'a> From<&'a [&str]> for PropertyValue {
     fn from(value: &'a [&str]) -> Self {
-        PropertyValue::Array(value.iter().map(|s| s.to_string()).collect())
+        PropertyValue::Array(value.iter().map(|s| ArrayElement::String(s.to_string())).collect())
+    }
+}
+
+// Implement conversions to ArrayElement
+impl From<String> for ArrayElement {
+    fn from(value: String) -> Self {
+        ArrayElement::String(value)
+    }
+}
+
+impl From<&str> for ArrayElement {
+    fn from(value: &str) -> Self {
+        ArrayElement::String(value.to_string())
+    }
+}
+
+impl From<i64> for ArrayElement {
+    fn from(value: i64) -> Self {
+        ArrayElement::Number(value)
+    }
+}
+
+impl From<i32> for ArrayElement {
+    fn from(value: i32) -> Self {
+        ArrayElement::Number(value as i64)
+    }
+}
+
+impl From<f64> for ArrayElement {
+    fn from(value: f64) -> Self {
+        ArrayElement::Float(value)
+    }
+}
+
+impl From<f32> for ArrayElement {
+    fn from(value: f32) -> Self {
+        // See `From<f32> for PropertyValue`
+        ArrayElement::Float(value.to_string().parse().unwrap_or(value as f64))
+    }
+}
+
+impl From<Vec<ArrayElement>> for ArrayElement {
+    fn from(value: Vec<ArrayElement>) -> Self {
+        ArrayElement::Array(value)
     }
 }
 
@@ -53,6 +117,9 @@ pub struct GameClassBuilder {
     file_path: PathBuf,
     container_class: Option<String>,
     properties: Vec<ClassProperty>,
+    span: Option<SourceSpan>,
+    kind: ClassKind,
+    fidelity: Fidelity,
 }
 
 impl GameClassBuilder {
@@ -63,6 +130,9 @@ impl GameClassBuilder {
             file_path: PathBuf::new(),
             container_class: None,
             properties: Vec::new(),
+            span: None,
+            kind: ClassKind::Definition,
+            fidelity: Fidelity::Full,
         }
     }
     
@@ -82,10 +152,22 @@ impl GameClassBuilder {
     }
     
     pub fn add_property(mut self, name: impl Into<String>, value: impl Into<PropertyValue>) -> Self {
-        self.properties.push(ClassProperty {
-            name: name.into(),
-            value: value.into(),
-        });
+        self.properties.push(ClassProperty::new(name.into(), value.into()));
+        self
+    }
+    
+    pub fn span(mut self, span: SourceSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+    
+    pub fn kind(mut self, kind: ClassKind) -> Self {
+        self.kind = kind;
+        self
+    }
+    
+    pub fn fidelity(mut self, fidelity: Fidelity) -> Self {
+        self.fidelity = fidelity;
         self
     }
     
@@ -96,6 +178,9 @@ impl GameClassBuilder {
             file_path: self.file_path,
             container_class: self.container_class,
             properties: self.properties,
+            span: self.span,
+            kind: self.kind,
+            fidelity: self.fidelity,
         }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,212 @@
+//! Structured errors reported while scanning files
+//!
+//! Every failure carries a [`Diagnostic`] with the file, location (if known),
+//! severity, message and the hemtt code ident, so callers can categorize and
+//! report errors without matching on message text.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::SourceSpan;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Severity {
+    /// The file could not be scanned
+    #[default]
+    Error,
+
+    /// The file was scanned, but something looks wrong
+    Warning,
+
+    /// Additional information, e.g. a suggestion
+    Help,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "Error"),
+            Severity::Warning => write!(f, "Warning"),
+            Severity::Help => write!(f, "Help"),
+        }
+    }
+}
+
+/// A message about a file, with its location if known
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// File the diagnostic is about
+    pub file: PathBuf,
+
+    /// Location in `file` (or in a file it includes)
+    pub span: Option<SourceSpan>,
+
+    /// How serious the diagnostic is
+    pub severity: Severity,
+
+    /// Human-readable message
+    pub message: String,
+
+    /// hemtt code ident (e.g. `PE12`), if the diagnostic came from hemtt
+    pub code: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create an error diagnostic without location or code
+    pub fn new(file: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            span: None,
+            severity: Severity::Error,
+            message: message.into(),
+            code: None,
+        }
+    }
+
+    /// Set the location
+    pub fn with_span(mut self, span: SourceSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Set the severity
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Set the hemtt code ident
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Line the diagnostic points at (1-based), if known
+    pub fn line(&self) -> Option<usize> {
+        self.span.as_ref().map(|span| span.line)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.span {
+            Some(span) => write!(f, "{}: ", span)?,
+            None => write!(f, "{}: ", self.file.display())?,
+        }
+        if let Some(code) = &self.code {
+            write!(f, "[{}] ", code)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Why a file could not be scanned
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanError {
+    /// The file (or the workspace around it) could not be read
+    Io(Diagnostic),
+
+    /// The preprocessor failed, e.g. on a missing include or a bad macro
+    Preprocess(Diagnostic),
+
+    /// The preprocessed text is not a valid config
+    Parse(Diagnostic),
+
+    /// Processing took longer than the configured limit
+    Timeout(Diagnostic),
+
+    /// The parser panicked while processing the file
+    Panicked(Diagnostic),
+
+    /// The file exceeds the configured size limit
+    TooLarge(Diagnostic),
+}
+
+impl ScanError {
+    /// Create an IO error for a file
+    pub fn io(file: &Path, error: &io::Error) -> Self {
+        ScanError::Io(Diagnostic::new(file, error.to_string()))
+    }
+
+    /// Create a timeout error for a file
+    pub fn timeout(file: &Path, timeout_secs: u64) -> Self {
+        ScanError::Timeout(Diagnostic::new(file, format!("Processing timed out after {} seconds", timeout_secs)))
+    }
+
+    /// Create an error for a parser that panicked or otherwise died without a result
+    pub fn panicked(file: &Path, message: impl Into<String>) -> Self {
+        ScanError::Panicked(Diagnostic::new(file, message))
+    }
+
+    /// Create a size limit error for a file
+    pub fn too_large(file: &Path, size: u64, max_size: u64) -> Self {
+        ScanError::TooLarge(Diagnostic::new(file, format!("File too large: {} bytes (max: {} bytes)", size, max_size)))
+    }
+
+    /// The diagnostic describing the error
+    pub fn diagnostic(&self) -> &Diagnostic {
+        match self {
+            ScanError::Io(diagnostic)
+            | ScanError::Preprocess(diagnostic)
+            | ScanError::Parse(diagnostic)
+            | ScanError::Timeout(diagnostic)
+            | ScanError::Panicked(diagnostic)
+            | ScanError::TooLarge(diagnostic) => diagnostic,
+        }
+    }
+
+    /// Mutable access to the diagnostic, e.g. to rewrite its file path
+    pub fn diagnostic_mut(&mut self) -> &mut Diagnostic {
+        match self {
+            ScanError::Io(diagnostic)
+            | ScanError::Preprocess(diagnostic)
+            | ScanError::Parse(diagnostic)
+            | ScanError::Timeout(diagnostic)
+            | ScanError::Panicked(diagnostic)
+            | ScanError::TooLarge(diagnostic) => diagnostic,
+        }
+    }
+
+    /// File the error is about
+    pub fn file(&self) -> &Path {
+        &self.diagnostic().file
+    }
+
+    /// Human-readable message
+    pub fn message(&self) -> &str {
+        &self.diagnostic().message
+    }
+
+    /// How serious the error is
+    pub fn severity(&self) -> Severity {
+        self.diagnostic().severity
+    }
+
+    /// Check if the error is a timeout
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, ScanError::Timeout(_))
+    }
+
+    /// Category name for reports, e.g. `Syntax Error`
+    pub fn category(&self) -> &'static str {
+        match self {
+            ScanError::Io(_) => "IO Error",
+            ScanError::Preprocess(_) => "Preprocessor Error",
+            ScanError::Parse(_) => "Syntax Error",
+            ScanError::Timeout(_) => "Timeout Error",
+            ScanError::Panicked(_) => "Parser Crash",
+            ScanError::TooLarge(_) => "Size Error",
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.category(), self.diagnostic())
+    }
+}
+
+impl std::error::Error for ScanError {}
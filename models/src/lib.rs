@@ -4,6 +4,17 @@ use serde::{Serialize, Deserialize};
 
 // Include conversion modules
 pub mod conversions;
+pub mod config_tree;
+pub mod error;
+pub mod load_order;
+pub mod preprocessor;
+pub mod writer;
+
+pub use config_tree::{ConfigNode, ConfigTree, ConfigTreeError, EffectiveProperty, NodeId};
+pub use error::{Diagnostic, ScanError, Severity};
+pub use load_order::{AddonPatch, LoadOrder, LoadOrderError};
+pub use preprocessor::{IncludeRecord, MacroDefinition, MacroExpansion, ParseStatus, ParsedFile, PreprocessorInfo};
+pub use writer::{write_class, write_classes, WriteError, WriterOptions};
 
 /// Interface for file parsers
 pub trait FileParser: Send + Sync {
@@ -34,6 +45,18 @@ pub struct GameClass {
     
     /// Properties of the class
     pub properties: Vec<ClassProperty>,
+    
+    /// Where the class was declared (if known)
+    #[serde(default)]
+    pub span: Option<SourceSpan>,
+    
+    /// Whether this is a definition, an external declaration or a deletion
+    #[serde(default)]
+    pub kind: ClassKind,
+    
+    /// How much of the class the parser could extract
+    #[serde(default)]
+    pub fidelity: Fidelity,
 }
 
 impl GameClass {
@@ -45,6 +68,9 @@ impl GameClass {
             file_path,
             container_class: None,
             properties: Vec::new(),
+            span: None,
+            kind: ClassKind::Definition,
+            fidelity: Fidelity::Full,
         }
     }
     
@@ -61,12 +87,15 @@ impl GameClass {
             file_path,
             container_class,
             properties: Vec::new(),
+            span: None,
+            kind: ClassKind::Definition,
+            fidelity: Fidelity::Full,
         }
     }
     
     /// Add a property to the class
     pub fn add_property(&mut self, name: String, value: PropertyValue) {
-        self.properties.push(ClassProperty { name, value });
+        self.properties.push(ClassProperty::new(name, value));
     }
     
     /// Set the container class
@@ -78,6 +107,45 @@ impl GameClass {
     pub fn is_child_of(&self, parent_name: &str) -> bool {
         self.parent.as_ref().map_or(false, |p| p == parent_name)
     }
+    
+    /// Check if this is a class definition rather than an external declaration or deletion
+    pub fn is_definition(&self) -> bool {
+        self.kind == ClassKind::Definition
+    }
+    
+    /// Check if the class has [`Fidelity::Low`]
+    pub fn is_low_fidelity(&self) -> bool {
+        self.fidelity == Fidelity::Low
+    }
+}
+
+/// What a class statement does to the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ClassKind {
+    /// A class with a body, `class Foo: Bar { ... };`
+    #[default]
+    Definition,
+    
+    /// A reference to a class defined elsewhere, `class Foo;`
+    External,
+    
+    /// A deletion of a previously defined class, `delete Foo;`
+    Deleted,
+}
+
+/// How much of a class the parser could extract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Fidelity {
+    /// Fully parsed, with properties and nested classes
+    #[default]
+    Full,
+    
+    /// Found without preprocessing, as by the simple parser
+    ///
+    /// Name, parent, kind and nesting are reliable. Only properties with a
+    /// plain string or number value are present; arrays, expressions and
+    /// anything produced by macros or includes are missing.
+    Low,
 }
 
 /// Represents a property of a class
@@ -88,6 +156,71 @@ pub struct ClassProperty {
     
     /// Property value
     pub value: PropertyValue,
+    
+    /// Where the property was declared (if known)
+    #[serde(default)]
+    pub span: Option<SourceSpan>,
+    
+    /// Whether the value replaces (`=`) or extends (`+=`) the inherited one
+    #[serde(default)]
+    pub operator: PropertyOperator,
+}
+
+impl ClassProperty {
+    /// Create a new property without source information
+    pub fn new(name: String, value: PropertyValue) -> Self {
+        Self {
+            name,
+            value,
+            span: None,
+            operator: PropertyOperator::Assign,
+        }
+    }
+    
+    /// Check if the property appends to the inherited array (`+=`)
+    pub fn is_append(&self) -> bool {
+        self.operator == PropertyOperator::Append
+    }
+}
+
+/// How a property declaration combines with an inherited value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PropertyOperator {
+    /// `name = value;` replaces the inherited value
+    #[default]
+    Assign,
+    
+    /// `name[] += {...};` appends to the inherited array
+    Append,
+}
+
+/// Location of a definition in a source file
+///
+/// For parsers that run a preprocessor this points into the original file,
+/// not into the expanded output, so definitions pulled in through `#include`
+/// report the included file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    /// File containing the definition
+    pub file: PathBuf,
+    
+    /// Line number (1-based)
+    pub line: usize,
+    
+    /// Column number (1-based)
+    pub column: usize,
+    
+    /// Byte offset of the start of the definition within `file`
+    pub start: usize,
+    
+    /// Byte offset of the end of the definition within `file`
+    pub end: usize,
+}
+
+impl std::fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
 }
 
 /// Represents a property value
@@ -96,14 +229,25 @@ pub enum PropertyValue {
     /// String value
     String(String),
     
-    /// Array of strings
-    Array(Vec<String>),
+    /// Array of typed elements
+    Array(Vec<ArrayElement>),
     
-    /// Numeric value
+    /// Integer value
     Number(i64),
     
-    /// Nested class
+    /// Floating-point value
+    Float(f64),
+    
+    /// Nested class (including external declarations and deletions, see [`ClassKind`])
     Class(Box<GameClass>),
+    
+    /// Expression evaluated by the game, e.g. `__EVAL(2 * 3)`
+    Expression {
+        /// Expression text as written (after preprocessing)
+        source: String,
+        /// Numeric result, if the expression is simple enough to evaluate
+        evaluated: Option<f64>,
+    },
 }
 
 impl PropertyValue {
@@ -116,7 +260,7 @@ impl PropertyValue {
     }
     
     /// Get the value as an array, if it's an array
-    pub fn as_array(&self) -> Option<&[String]> {
+    pub fn as_array(&self) -> Option<&[ArrayElement]> {
         match self {
             PropertyValue::Array(arr) => Some(arr),
             _ => None,
@@ -131,6 +275,32 @@ impl PropertyValue {
         }
     }
     
+    /// Get the value as a float, if it's a float
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            PropertyValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+    
+    /// Get any numeric value (integer, float or evaluated expression) widened to f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            PropertyValue::Number(n) => Some(*n as f64),
+            PropertyValue::Float(f) => Some(*f),
+            PropertyValue::Expression { evaluated, .. } => *evaluated,
+            _ => None,
+        }
+    }
+    
+    /// Get the expression text, if it's an expression
+    pub fn as_expression(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Expression { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+    
     /// Get the value as a class, if it's a class
     pub fn as_class(&self) -> Option<&GameClass> {
         match self {
@@ -140,6 +310,84 @@ impl PropertyValue {
     }
 }
 
+/// Represents a single element of an array property
+///
+/// Arrays in config files can mix strings and numbers and nest arbitrarily,
+/// e.g. `magazineWell[] = {{"a", 1}, {"b", 2}};`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ArrayElement {
+    /// String element
+    String(String),
+    
+    /// Integer element
+    Number(i64),
+    
+    /// Floating-point element
+    Float(f64),
+    
+    /// Nested array
+    Array(Vec<ArrayElement>),
+    
+    /// Macro call the preprocessor could not expand
+    Macro {
+        /// Macro name
+        name: String,
+        /// Raw macro arguments
+        args: Vec<String>,
+    },
+}
+
+impl ArrayElement {
+    /// Get the element as a string, if it's a string
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            ArrayElement::String(s) => Some(s),
+            _ => None,
+        }
+    }
+    
+    /// Get the element as a number, if it's a number
+    pub fn as_number(&self) -> Option<i64> {
+        match self {
+            ArrayElement::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+    
+    /// Get the element as a float, if it's a float
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            ArrayElement::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+    
+    /// Get any numeric element (integer or float) widened to f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ArrayElement::Number(n) => Some(*n as f64),
+            ArrayElement::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+    
+    /// Get the element as a nested array, if it's an array
+    pub fn as_array(&self) -> Option<&[ArrayElement]> {
+        match self {
+            ArrayElement::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+    
+    /// Get the macro name, if the element is an unexpanded macro
+    pub fn macro_name(&self) -> Option<&str> {
+        match self {
+            ArrayElement::Macro { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
 /// Result of scanning a directory or file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResult {
@@ -220,6 +468,34 @@ impl ScanResult {
     pub fn find_class(&self, name: &str) -> Option<&Vec<GameClass>> {
         self.class_map.get(name)
     }
+    
+    /// Addon load order from the scanned `CfgPatches` classes
+    pub fn load_order(&self) -> LoadOrder {
+        LoadOrder::from_classes(self.sorted_classes())
+    }
+    
+    /// Merge all top-level classes into a [`ConfigTree`] and resolve inheritance
+    ///
+    /// Classes are merged in addon load order (see [`ScanResult::load_order`]).
+    pub fn config_tree(&self) -> ConfigTree {
+        let mut classes = self.sorted_classes();
+        self.load_order().sort_classes(&mut classes);
+        ConfigTree::from_classes(classes)
+    }
+    
+    /// All classes ordered by file, then by position within the file
+    ///
+    /// Classes without a span (e.g. from rapified files) are ordered by name,
+    /// and classes of the same name keep the order they were added in.
+    fn sorted_classes(&self) -> Vec<&GameClass> {
+        let mut names: Vec<&String> = self.class_map.keys().collect();
+        names.sort();
+        let mut classes: Vec<&GameClass> = names.into_iter()
+            .flat_map(|name| &self.class_map[name])
+            .collect();
+        classes.sort_by_key(|class| (&class.file_path, class.span.as_ref().map(|span| span.start)));
+        classes
+    }
 }
 
 /// Common trait for all scanner implementations
@@ -228,7 +504,7 @@ pub trait Scanner: Send + Sync {
     fn scan_directory(&self, dir_path: &Path) -> Result<ScanResult, String>;
     
     /// Parse a single file for class definitions
-    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, String>;
+    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>>;
 }
 
 /// Utility module with conversion functions
@@ -250,6 +526,9 @@ pub mod convert {
             file_path,
             container_class,
             properties: Vec::new(),
+            span: None,
+            kind: ClassKind::Definition,
+            fidelity: Fidelity::Full,
         }
     }
     
@@ -265,10 +544,104 @@ pub mod convert {
         F: Fn(&T) -> String
     {
         properties.iter()
-            .map(|prop| ClassProperty {
-                name: name_fn(prop),
-                value: value_fn(prop),
-            })
+            .map(|prop| ClassProperty::new(name_fn(prop), value_fn(prop)))
             .collect()
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversions::GameClassBuilder;
+
+    fn span(file: &str, start: usize) -> SourceSpan {
+        SourceSpan { file: PathBuf::from(file), line: 1, column: 1, start, end: start + 1 }
+    }
+
+    /// `class CfgVehicles { class Car { speed = <speed>; }; };` declared at `start` in `file`
+    fn vehicles(file: &str, start: usize, speed: i64) -> GameClass {
+        let car = GameClassBuilder::new("Car")
+            .file_path(file)
+            .container_class("CfgVehicles")
+            .add_property("speed", speed)
+            .build();
+        GameClassBuilder::new("CfgVehicles")
+            .file_path(file)
+            .span(span(file, start))
+            .add_property("Car", PropertyValue::Class(Box::new(car)))
+            .build()
+    }
+
+    /// `class CfgPatches { class <name> { requiredAddons[] = {...}; }; };` in `file`
+    fn patches(file: &str, name: &str, required: &[&str]) -> GameClass {
+        let required: Vec<ArrayElement> = required.iter().map(|addon| ArrayElement::String(addon.to_string())).collect();
+        let patch = GameClassBuilder::new(name)
+            .file_path(file)
+            .container_class("CfgPatches")
+            .add_property("requiredAddons", required)
+            .build();
+        GameClassBuilder::new("CfgPatches")
+            .file_path(file)
+            .span(span(file, 0))
+            .add_property(name, PropertyValue::Class(Box::new(patch)))
+            .build()
+    }
+
+    fn speed(tree: &ConfigTree) -> Option<f64> {
+        tree.get("CfgVehicles/Car")?.property("speed")?.value.as_f64()
+    }
+
+    #[test]
+    fn test_config_tree_follows_load_order() {
+        // `a_patch` sorts first by path but requires `base`, so its values win
+        let classes = vec![
+            patches("addons/base/config.cpp", "base", &[]),
+            vehicles("addons/base/config.cpp", 100, 1),
+            patches("addons/a_patch/config.cpp", "a_patch", &["base"]),
+            vehicles("addons/a_patch/config.cpp", 100, 2),
+        ];
+
+        for reversed in [false, true] {
+            let mut result = ScanResult::new();
+            if reversed {
+                result.add_classes(classes.iter().rev().cloned().collect());
+            } else {
+                result.add_classes(classes.clone());
+            }
+
+            let load_order = result.load_order();
+            let order: Vec<&str> = load_order.addons.iter().map(|patch| patch.name.as_str()).collect();
+            assert_eq!(order, vec!["base", "a_patch"]);
+
+            let tree = result.config_tree();
+            assert_eq!(speed(&tree), Some(2.0));
+            assert_eq!(tree.get("CfgVehicles/Car").unwrap().sources, vec![
+                PathBuf::from("addons/base/config.cpp"),
+                PathBuf::from("addons/a_patch/config.cpp"),
+            ]);
+        }
+    }
+
+    #[test]
+    fn test_config_tree_same_name_in_one_file() {
+        // The later definition in the file wins, whatever order the classes were added in
+        let file = "addons/main/config.cpp";
+        for (first, second) in [(0, 200), (200, 0)] {
+            let mut result = ScanResult::new();
+            result.add_class(vehicles(file, first, 1));
+            result.add_class(vehicles(file, second, 2));
+
+            let expected = if second > first { 2.0 } else { 1.0 };
+            assert_eq!(speed(&result.config_tree()), Some(expected));
+        }
+
+        // Without spans, classes of the same name keep the order they were added in
+        let mut result = ScanResult::new();
+        for speed in [1, 2, 3] {
+            let mut class = vehicles(file, 0, speed);
+            class.span = None;
+            result.add_class(class);
+        }
+        assert_eq!(speed(&result.config_tree()), Some(3.0));
+    }
+}
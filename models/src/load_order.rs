@@ -0,0 +1,284 @@
+//! Addon load order from `CfgPatches >> requiredAddons`
+//!
+//! The game merges addon configs in dependency order: an addon is loaded after
+//! every addon listed in its `requiredAddons[]`. When two addons redefine the
+//! same class, the one loaded last wins. [`LoadOrder`] reproduces that order so
+//! classes can be merged into a [`ConfigTree`](crate::ConfigTree) the same way.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::{GameClass, PropertyValue};
+
+/// Name of the class holding addon definitions
+const CFG_PATCHES: &str = "CfgPatches";
+
+/// An addon declared in `CfgPatches`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddonPatch {
+    /// Class name inside `CfgPatches`
+    pub name: String,
+
+    /// Addons listed in `requiredAddons[]`
+    pub required_addons: Vec<String>,
+
+    /// File declaring the addon
+    pub file_path: PathBuf,
+}
+
+/// Problems found while ordering addons
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoadOrderError {
+    /// An addon requires an addon that was not scanned
+    ///
+    /// This is expected for dependencies outside the scanned tree, such as the
+    /// base game's `A3_*` addons.
+    MissingDependency {
+        /// Addon declaring the requirement
+        addon: String,
+        /// Required addon that was not found
+        dependency: String,
+    },
+
+    /// Addons that require each other, directly or indirectly
+    ///
+    /// They are loaded after all other addons, in the order they were found.
+    Cycle {
+        /// Addons that could not be ordered
+        addons: Vec<String>,
+    },
+}
+
+impl fmt::Display for LoadOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadOrderError::MissingDependency { addon, dependency } => {
+                write!(f, "Addon '{}' requires missing addon '{}'", addon, dependency)
+            }
+            LoadOrderError::Cycle { addons } => {
+                write!(f, "Circular addon dependencies between: {}", addons.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadOrderError {}
+
+/// Addons sorted so every addon comes after the addons it requires
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadOrder {
+    /// Addons in load order
+    pub addons: Vec<AddonPatch>,
+
+    /// Missing dependencies and cycles found while sorting
+    pub errors: Vec<LoadOrderError>,
+}
+
+impl LoadOrder {
+    /// Read `CfgPatches` from parsed classes and sort the addons
+    ///
+    /// Addons without ordering constraints between them keep the order they
+    /// were found in, so pass classes in a stable order.
+    pub fn from_classes<'a, I>(classes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a GameClass>,
+    {
+        Self::from_patches(find_patches(classes))
+    }
+
+    /// Sort a list of addons by their requirements
+    pub fn from_patches(patches: Vec<AddonPatch>) -> Self {
+        let mut errors = Vec::new();
+
+        let index: HashMap<String, usize> = patches.iter()
+            .enumerate()
+            .map(|(i, patch)| (patch.name.to_lowercase(), i))
+            .collect();
+
+        // Number of unloaded requirements per addon, and who is waiting on whom
+        let mut pending = vec![0usize; patches.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); patches.len()];
+        for (i, patch) in patches.iter().enumerate() {
+            let mut seen = HashSet::new();
+            for dependency in &patch.required_addons {
+                if !seen.insert(dependency.to_lowercase()) {
+                    continue;
+                }
+                match index.get(&dependency.to_lowercase()) {
+                    Some(&required) if required != i => {
+                        pending[i] += 1;
+                        dependents[required].push(i);
+                    }
+                    Some(_) => {}
+                    None => errors.push(LoadOrderError::MissingDependency {
+                        addon: patch.name.clone(),
+                        dependency: dependency.clone(),
+                    }),
+                }
+            }
+        }
+
+        // Kahn's algorithm, always taking the earliest ready addon to keep the order stable
+        let mut order = Vec::with_capacity(patches.len());
+        let mut loaded = vec![false; patches.len()];
+        while let Some(next) = (0..patches.len()).find(|&i| !loaded[i] && pending[i] == 0) {
+            loaded[next] = true;
+            order.push(next);
+            for &dependent in &dependents[next] {
+                pending[dependent] -= 1;
+            }
+        }
+
+        let cyclic: Vec<usize> = (0..patches.len()).filter(|&i| !loaded[i]).collect();
+        if !cyclic.is_empty() {
+            errors.push(LoadOrderError::Cycle {
+                addons: cyclic.iter().map(|&i| patches[i].name.clone()).collect(),
+            });
+            order.extend(cyclic);
+        }
+
+        let mut patches: Vec<Option<AddonPatch>> = patches.into_iter().map(Some).collect();
+        let addons = order.into_iter()
+            .filter_map(|i| patches[i].take())
+            .collect();
+
+        Self { addons, errors }
+    }
+
+    /// Position of an addon in the load order (case-insensitive)
+    pub fn position(&self, addon: &str) -> Option<usize> {
+        self.addons.iter().position(|patch| patch.name.eq_ignore_ascii_case(addon))
+    }
+
+    /// Position of the addon a file belongs to
+    ///
+    /// A file belongs to the addon declared in the closest directory above it
+    /// (usually the addon's `config.cpp`). When one directory declares several
+    /// addons, the last of them in load order is used. Returns `None` for files
+    /// outside any addon.
+    pub fn file_position(&self, file_path: &Path) -> Option<usize> {
+        let mut best: Option<(usize, usize)> = None;
+        for (position, patch) in self.addons.iter().enumerate() {
+            let Some(addon_dir) = patch.file_path.parent() else {
+                continue;
+            };
+            if !file_path.starts_with(addon_dir) {
+                continue;
+            }
+
+            // Deepest directory wins; later addons win within the same directory
+            let depth = addon_dir.components().count();
+            match best {
+                Some((best_depth, _)) if best_depth > depth => {}
+                _ => best = Some((depth, position)),
+            }
+        }
+        best.map(|(_, position)| position)
+    }
+
+    /// Sort classes into merge order
+    ///
+    /// Classes from files outside any addon come first, then each addon's
+    /// classes in load order. The sort is stable, so classes from the same
+    /// addon keep their relative order.
+    pub fn sort_classes(&self, classes: &mut [&GameClass]) {
+        let mut positions: HashMap<&Path, Option<usize>> = HashMap::new();
+        for &class in classes.iter() {
+            positions.entry(class.file_path.as_path())
+                .or_insert_with(|| self.file_position(&class.file_path));
+        }
+        classes.sort_by_key(|class| positions[class.file_path.as_path()]);
+    }
+}
+
+/// Collect the addons declared in `CfgPatches` classes
+///
+/// Patches are taken from the nested classes of top-level `CfgPatches` classes,
+/// as well as from flat classes whose container is `CfgPatches` for parsers
+/// that don't nest classes.
+fn find_patches<'a, I>(classes: I) -> Vec<AddonPatch>
+where
+    I: IntoIterator<Item = &'a GameClass>,
+{
+    let mut patches: Vec<AddonPatch> = Vec::new();
+    let mut add = |class: &GameClass| {
+        let duplicate = patches.iter().any(|patch| {
+            patch.name.eq_ignore_ascii_case(&class.name) && patch.file_path == class.file_path
+        });
+        if !duplicate {
+            patches.push(AddonPatch {
+                name: class.name.clone(),
+                required_addons: required_addons(class),
+                file_path: class.file_path.clone(),
+            });
+        }
+    };
+
+    for class in classes {
+        match &class.container_class {
+            None if class.name.eq_ignore_ascii_case(CFG_PATCHES) => {
+                class.properties.iter()
+                    .filter_map(|property| property.value.as_class())
+                    .filter(|patch| patch.is_definition())
+                    .for_each(&mut add);
+            }
+            Some(container) if container.eq_ignore_ascii_case(CFG_PATCHES) && class.is_definition() => add(class),
+            _ => {}
+        }
+    }
+
+    patches
+}
+
+/// Read `requiredAddons[]` from a patch class
+fn required_addons(class: &GameClass) -> Vec<String> {
+    class.properties.iter()
+        .find(|property| property.name.eq_ignore_ascii_case("requiredAddons"))
+        .and_then(|property| match &property.value {
+            PropertyValue::Array(items) => Some(
+                items.iter()
+                    .filter_map(|item| item.as_string())
+                    .map(str::to_string)
+                    .collect()
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patch(name: &str, required: &[&str]) -> AddonPatch {
+        AddonPatch {
+            name: name.to_string(),
+            required_addons: required.iter().map(|addon| addon.to_string()).collect(),
+            file_path: PathBuf::from("addons").join(name).join("config.cpp"),
+        }
+    }
+
+    #[test]
+    fn test_sort_patches() {
+        let order = LoadOrder::from_patches(vec![
+            patch("ui", &["core", "A3_Data_F"]),
+            patch("core", &[]),
+            patch("loop_a", &["loop_b"]),
+            patch("loop_b", &["LOOP_A"]),
+            patch("weapons", &["core"]),
+        ]);
+
+        let names: Vec<&str> = order.addons.iter().map(|patch| patch.name.as_str()).collect();
+        assert_eq!(names, vec!["core", "ui", "weapons", "loop_a", "loop_b"]);
+        assert_eq!(order.errors, vec![
+            LoadOrderError::MissingDependency { addon: "ui".to_string(), dependency: "A3_Data_F".to_string() },
+            LoadOrderError::Cycle { addons: vec!["loop_a".to_string(), "loop_b".to_string()] },
+        ]);
+
+        assert_eq!(order.position("UI"), Some(1));
+        assert_eq!(order.file_position(Path::new("addons/weapons/data/rifle.hpp")), Some(2));
+        assert_eq!(order.file_position(Path::new("tools/config.cpp")), None);
+    }
+}
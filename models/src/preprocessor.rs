@@ -0,0 +1,121 @@
+//! Records of what the preprocessor did to a file
+//!
+//! Parsers only return the classes found after preprocessing. These types keep
+//! the macros, their uses and the followed includes, so it's possible to audit
+//! macro usage and explain where a value came from.
+
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+
+use crate::{Diagnostic, GameClass, ScanError};
+
+/// A `#define` directive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroDefinition {
+    /// Macro name
+    pub name: String,
+
+    /// Parameter names for function-like macros, `None` for object-like ones
+    pub params: Option<Vec<String>>,
+
+    /// Replacement text, with line continuations joined
+    pub body: String,
+
+    /// File containing the definition
+    pub file: PathBuf,
+
+    /// Line of the `#define` (1-based)
+    pub line: usize,
+}
+
+/// A place where a macro is used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroExpansion {
+    /// Name of the expanded macro
+    pub name: String,
+
+    /// File containing the use
+    pub file: PathBuf,
+
+    /// Line of the use (1-based)
+    pub line: usize,
+}
+
+/// An `#include` directive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncludeRecord {
+    /// Path as written in the directive, e.g. `\x\cba\addons\main\script_macros_common.hpp`
+    pub path: String,
+
+    /// File on disk the include resolved to, `None` if it could not be found
+    pub resolved: Option<PathBuf>,
+
+    /// File containing the directive
+    pub file: PathBuf,
+
+    /// Line of the directive (1-based)
+    pub line: usize,
+}
+
+/// Everything the preprocessor saw while processing a file and its includes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreprocessorInfo {
+    /// `#define`s in the order they were seen
+    pub defines: Vec<MacroDefinition>,
+
+    /// Macro uses in the order they were seen
+    pub expansions: Vec<MacroExpansion>,
+
+    /// `#include`s in the order they were seen
+    pub includes: Vec<IncludeRecord>,
+}
+
+impl PreprocessorInfo {
+    /// Find the last definition of a macro
+    pub fn definition(&self, name: &str) -> Option<&MacroDefinition> {
+        self.defines.iter().rev().find(|define| define.name == name)
+    }
+
+    /// All uses of a macro
+    pub fn expansions_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a MacroExpansion> {
+        self.expansions.iter().filter(move |expansion| expansion.name == name)
+    }
+
+    /// Includes that could not be resolved
+    pub fn missing_includes(&self) -> impl Iterator<Item = &IncludeRecord> {
+        self.includes.iter().filter(|include| include.resolved.is_none())
+    }
+}
+
+/// Classes of a file together with its preprocessor record
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParsedFile {
+    /// Classes found in the file
+    pub classes: Vec<GameClass>,
+
+    /// What the preprocessor did while processing the file
+    pub preprocessor: PreprocessorInfo,
+
+    /// Warnings and other non-fatal issues reported while parsing the file
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// Whether all classes of the file were read
+    #[serde(default)]
+    pub status: ParseStatus,
+
+    /// Errors of the parts that were skipped, for partial parses
+    #[serde(default)]
+    pub errors: Vec<ScanError>,
+}
+
+/// How much of a file could be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ParseStatus {
+    /// The whole file was parsed
+    #[default]
+    Complete,
+
+    /// Some classes had errors and were skipped, the rest was parsed
+    Partial,
+}
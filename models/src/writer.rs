@@ -0,0 +1,202 @@
+//! Text serializer for classes, the inverse of the config parsers
+//!
+//! Produces config syntax the game and the advanced parser accept:
+//! `class Name: Parent { ... };`, `name[] = {...};` for arrays, `+=` for
+//! appending properties, `class Name;` for external declarations and
+//! `delete Name;` for deletions. Quotes in strings are escaped by doubling
+//! them. Values the syntax can't express, NaN and infinite floats, are
+//! rejected rather than written as text no parser reads back.
+
+use std::fmt::{self, Write};
+
+use crate::{ArrayElement, ClassKind, ClassProperty, GameClass, PropertyOperator, PropertyValue};
+
+/// Formatting options for [`write_classes`] and [`write_class`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriterOptions {
+    /// Text used for one level of indentation (default: four spaces)
+    pub indent: String,
+    /// Sort top-level classes, properties and nested classes by name
+    /// (case-insensitive), values before classes; otherwise declaration
+    /// order is preserved
+    pub sort_properties: bool,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        Self {
+            indent: "    ".to_string(),
+            sort_properties: false,
+        }
+    }
+}
+
+/// A value that has no config text representation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteError {
+    /// Path of the offending property, e.g. `CfgVehicles/Car/speed`
+    pub path: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot write `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// Write classes as config text
+///
+/// Only classes without a container are written at the top level, so the
+/// flattened output of a parser can be passed as is; nested classes are
+/// taken from the [`PropertyValue::Class`] properties of their container.
+pub fn write_classes(classes: &[GameClass], options: &WriterOptions) -> Result<String, WriteError> {
+    let mut out = String::new();
+    let top_level: Vec<&GameClass> = classes.iter()
+        .filter(|class| class.container_class.is_none())
+        .collect();
+    for class in sorted(top_level, options, |class| &class.name) {
+        write_class_at(&mut out, class, "", 0, options)?;
+    }
+    Ok(out)
+}
+
+/// Write a single class, with its nested classes, as config text
+pub fn write_class(class: &GameClass, options: &WriterOptions) -> Result<String, WriteError> {
+    let mut out = String::new();
+    write_class_at(&mut out, class, "", 0, options)?;
+    Ok(out)
+}
+
+/// Quote a string, escaping `"` as `""`
+pub fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn write_class_at(out: &mut String, class: &GameClass, path: &str, depth: usize, options: &WriterOptions) -> Result<(), WriteError> {
+    let indent = options.indent.repeat(depth);
+    match class.kind {
+        ClassKind::External => {
+            let _ = writeln!(out, "{}class {};", indent, class.name);
+            return Ok(());
+        }
+        ClassKind::Deleted => {
+            let _ = writeln!(out, "{}delete {};", indent, class.name);
+            return Ok(());
+        }
+        ClassKind::Definition => {}
+    }
+    let path = join(path, &class.name);
+
+    let _ = write!(out, "{}class {}", indent, class.name);
+    if let Some(parent) = &class.parent {
+        let _ = write!(out, ": {}", parent);
+    }
+    if class.properties.is_empty() {
+        out.push_str(" {};\n");
+        return Ok(());
+    }
+
+    out.push_str(" {\n");
+    let properties: Vec<&ClassProperty> = class.properties.iter().collect();
+    let properties = if options.sort_properties {
+        // Values first, then classes, each group by name
+        let (mut values, classes): (Vec<_>, Vec<_>) = sorted(properties, options, |property| &property.name)
+            .into_iter()
+            .partition(|property| property.value.as_class().is_none());
+        values.extend(classes);
+        values
+    } else {
+        properties
+    };
+    for property in properties {
+        write_property(out, property, &path, depth + 1, options)?;
+    }
+    let _ = writeln!(out, "{}}};", indent);
+    Ok(())
+}
+
+fn write_property(out: &mut String, property: &ClassProperty, path: &str, depth: usize, options: &WriterOptions) -> Result<(), WriteError> {
+    let indent = options.indent.repeat(depth);
+    match &property.value {
+        PropertyValue::Class(nested) => return write_class_at(out, nested, path, depth, options),
+        PropertyValue::Array(elements) => {
+            let operator = match property.operator {
+                PropertyOperator::Assign => "=",
+                PropertyOperator::Append => "+=",
+            };
+            let _ = write!(out, "{}{}[] {} ", indent, property.name, operator);
+            write_array(out, elements, &join(path, &property.name))?;
+            out.push_str(";\n");
+        }
+        PropertyValue::String(s) => {
+            let _ = writeln!(out, "{}{} = {};", indent, property.name, quote(s));
+        }
+        PropertyValue::Number(n) => {
+            let _ = writeln!(out, "{}{} = {};", indent, property.name, n);
+        }
+        PropertyValue::Float(f) => {
+            let value = format_float(*f, &join(path, &property.name))?;
+            let _ = writeln!(out, "{}{} = {};", indent, property.name, value);
+        }
+        PropertyValue::Expression { source, .. } => {
+            let _ = writeln!(out, "{}{} = {};", indent, property.name, source);
+        }
+    }
+    Ok(())
+}
+
+fn write_array(out: &mut String, elements: &[ArrayElement], path: &str) -> Result<(), WriteError> {
+    out.push('{');
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        match element {
+            ArrayElement::String(s) => out.push_str(&quote(s)),
+            ArrayElement::Number(n) => {
+                let _ = write!(out, "{}", n);
+            }
+            ArrayElement::Float(f) => out.push_str(&format_float(*f, path)?),
+            ArrayElement::Array(nested) => write_array(out, nested, path)?,
+            // Unexpanded macros are written back as calls
+            ArrayElement::Macro { name, args } if args.is_empty() => out.push_str(name),
+            ArrayElement::Macro { name, args } => {
+                let _ = write!(out, "{}({})", name, args.join(","));
+            }
+        }
+    }
+    out.push('}');
+    Ok(())
+}
+
+/// Format a float so it reads back as a float, e.g. `1.0` rather than `1`
+fn format_float(value: f64, path: &str) -> Result<String, WriteError> {
+    if !value.is_finite() {
+        return Err(WriteError {
+            path: path.to_string(),
+            message: format!("{} can't be written as a config number", value),
+        });
+    }
+    let text = value.to_string();
+    Ok(if text.contains('.') { text } else { format!("{}.0", text) })
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
+
+/// Items in declaration order, or sorted by name if requested
+fn sorted<'a, T>(mut items: Vec<&'a T>, options: &WriterOptions, name: impl Fn(&T) -> &str) -> Vec<&'a T> {
+    if options.sort_properties {
+        items.sort_by_cached_key(|item| name(item).to_lowercase());
+    }
+    items
+}
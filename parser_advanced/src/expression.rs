@@ -0,0 +1,221 @@
+//! Evaluation of simple numeric expressions in config values
+//!
+//! Configs use expressions for balancing values, either through `__EVAL(...)`
+//! or as bare formulas such as `2 * 3 + 1`. The game evaluates those with SQF;
+//! this covers the arithmetic subset: numbers, `+ - * / % ^`, parentheses, `pi`
+//! and references to numeric constants.
+
+use std::f64::consts::PI;
+
+/// Evaluate an expression, returning `None` if it uses anything beyond simple arithmetic
+///
+/// `lookup` resolves identifiers (e.g. other numeric properties of the class).
+pub fn evaluate(source: &str, lookup: impl Fn(&str) -> Option<f64>) -> Option<f64> {
+    let tokens = tokenize(strip_eval(source))?;
+    let mut parser = ExpressionParser { tokens: &tokens, pos: 0, lookup: &lookup };
+    let value = parser.expression()?;
+    if parser.pos != tokens.len() || !value.is_finite() {
+        return None;
+    }
+    Some(value)
+}
+
+/// Remove an `__EVAL(...)` wrapper
+fn strip_eval(source: &str) -> &str {
+    let trimmed = source.trim();
+    trimmed.strip_prefix("__EVAL")
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('('))
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(trimmed)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+fn tokenize(source: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => {
+                tokens.push(Token::Operator(c));
+                i += 1;
+            }
+            '0'..='9' | '.' => {
+                let start = i;
+                if c == '0' && matches!(chars.get(i + 1), Some('x' | 'X')) {
+                    i += 2;
+                    while i < chars.len() && chars[i].is_ascii_hexdigit() {
+                        i += 1;
+                    }
+                    let hex: String = chars[start + 2..i].iter().collect();
+                    tokens.push(Token::Number(i64::from_str_radix(&hex, 16).ok()? as f64));
+                    continue;
+                }
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // Exponent, e.g. 1e-3
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && matches!(chars[j], '+' | '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let number: String = chars[start..i].iter().collect();
+                tokens.push(Token::Number(number.parse().ok()?));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+struct ExpressionParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    lookup: &'a dyn Fn(&str) -> Option<f64>,
+}
+
+impl ExpressionParser<'_> {
+    fn peek_operator(&self, operators: &[char]) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Operator(op)) if operators.contains(op) => Some(*op),
+            // SQF spells modulo as `mod`
+            Some(Token::Ident(word)) if operators.contains(&'%') && word.eq_ignore_ascii_case("mod") => Some('%'),
+            _ => None,
+        }
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        while let Some(op) = self.peek_operator(&['+', '-']) {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Some(value)
+    }
+
+    /// term := power (('*' | '/' | '%') power)*
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.power()?;
+        while let Some(op) = self.peek_operator(&['*', '/', '%']) {
+            self.pos += 1;
+            let rhs = self.power()?;
+            value = match op {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Some(value)
+    }
+
+    /// power := unary ('^' unary)*
+    fn power(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        while self.peek_operator(&['^']).is_some() {
+            self.pos += 1;
+            value = value.powf(self.unary()?);
+        }
+        Some(value)
+    }
+
+    /// unary := ('-' | '+') unary | primary
+    fn unary(&mut self) -> Option<f64> {
+        match self.peek_operator(&['+', '-']) {
+            Some(op) => {
+                self.pos += 1;
+                let value = self.unary()?;
+                Some(if op == '-' { -value } else { value })
+            }
+            None => self.primary(),
+        }
+    }
+
+    /// primary := number | identifier | '(' expression ')'
+    fn primary(&mut self) -> Option<f64> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Some(value),
+            Token::Ident(name) if name.eq_ignore_ascii_case("pi") => Some(PI),
+            Token::Ident(name) => (self.lookup)(&name),
+            Token::Open => {
+                let value = self.expression()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::Close) => {
+                        self.pos += 1;
+                        Some(value)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_constants(_: &str) -> Option<f64> {
+        None
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(evaluate("2*3+1", no_constants), Some(7.0));
+        assert_eq!(evaluate("__EVAL(2 * (3 + 1))", no_constants), Some(8.0));
+        assert_eq!(evaluate("-2^2", no_constants), Some(4.0));
+        assert_eq!(evaluate("10 mod 4 - 0x10", no_constants), Some(-14.0));
+        assert_eq!(evaluate("1.5e1 / 3", no_constants), Some(5.0));
+        assert_eq!(evaluate("1/0", no_constants), None);
+        assert_eq!(evaluate("2 *", no_constants), None);
+        assert_eq!(evaluate("getNumber (configFile >> \"x\")", no_constants), None);
+    }
+
+    #[test]
+    fn test_constants() {
+        let lookup = |name: &str| name.eq_ignore_ascii_case("baseMass").then_some(10.0);
+        assert_eq!(evaluate("BASEMASS * 1.5", lookup), Some(15.0));
+        assert_eq!(evaluate("unknown + 1", lookup), None);
+        assert!((evaluate("pi / 2", lookup).unwrap() - PI / 2.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,363 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use log::{debug, warn};
+use tempfile::TempDir;
+
+/// Files that mark the root directory of an addon
+const ADDON_ROOT_MARKERS: &[&str] = &["$PBOPREFIX$", "$PBOPREFIX$.txt", "pboprefix.txt", "config.cpp"];
+
+/// Extensions copied when a prefix mapping can't be linked and has to be copied instead
+const INCLUDE_EXTENSIONS: &[&str] = &["hpp", "h", "hh", "inc", "cpp", "ext", "sqf"];
+
+/// Options controlling how `#include` directives are resolved
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Directory the preprocessor workspace is rooted at.
+    ///
+    /// Relative includes such as `#include "script_component.hpp"` resolve against
+    /// the including file inside this directory. When unset, the addon root of
+    /// the parsed file is used (see [`find_addon_root`]).
+    pub addon_root: Option<PathBuf>,
+
+    /// Directory being scanned; the search for an addon root stops here
+    pub scan_root: Option<PathBuf>,
+
+    /// Extra directories searched for absolute includes, laid out like a P-drive:
+    /// `#include "\x\cba\addons\main\script_macros_common.hpp"` resolves to
+    /// `<include root>/x/cba/addons/main/script_macros_common.hpp`
+    pub include_roots: Vec<PathBuf>,
+
+    /// Directory used as a virtual P-drive, searched after `include_roots`
+    pub pdrive: Option<PathBuf>,
+}
+
+impl ParseOptions {
+    /// Create options with no extra include roots
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Root the workspace at the given directory
+    pub fn with_addon_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.addon_root = Some(root.into());
+        self
+    }
+
+    /// Stop the search for addon roots at the given directory
+    pub fn with_scan_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.scan_root = Some(root.into());
+        self
+    }
+
+    /// Add an include root
+    pub fn with_include_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.include_roots.push(root.into());
+        self
+    }
+
+    /// Use a directory as virtual P-drive
+    pub fn with_pdrive(mut self, pdrive: impl Into<PathBuf>) -> Self {
+        self.pdrive = Some(pdrive.into());
+        self
+    }
+
+    /// All directories that should be searched for absolute includes, in order
+    pub fn search_roots(&self) -> Vec<&Path> {
+        self.include_roots.iter()
+            .chain(self.pdrive.iter())
+            .map(PathBuf::as_path)
+            .filter(|root| {
+                let exists = root.is_dir();
+                if !exists {
+                    debug!("Skipping missing include root: {}", root.display());
+                }
+                exists
+            })
+            .collect()
+    }
+}
+
+/// A temporary P-drive style directory exposing `\prefix\` mappings
+///
+/// hemtt resolves absolute includes against include roots laid out like a P-drive,
+/// so each mapping `z\ace\addons\main` -> `<dir>` is exposed as
+/// `<temp>/z/ace/addons/main` pointing at `<dir>`. Directories are symlinked where
+/// possible and otherwise their includable files are copied. A prefix nested in
+/// another one, e.g. `z\ace\addons\main\nested`, becomes a real directory
+/// holding links to the entries of the outer target next to the nested mapping,
+/// so neither target is written to. The directory is removed when this value is
+/// dropped.
+#[derive(Debug)]
+pub struct VirtualIncludeRoot {
+    dir: TempDir,
+}
+
+impl VirtualIncludeRoot {
+    /// Build a virtual include root from `(prefix, directory)` mappings
+    ///
+    /// Prefixes are compared case-insensitively; the first mapping of a prefix wins.
+    pub fn new<'a, I>(mappings: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = (&'a str, &'a Path)>,
+    {
+        let dir = TempDir::new()?;
+        
+        let mut root = PrefixNode::default();
+        for (prefix, target) in mappings {
+            let parts = split_prefix(prefix);
+            if parts.is_empty() {
+                continue;
+            }
+            let node = parts.iter().fold(&mut root, |node, part| node.child(part));
+            match node.target {
+                Some(existing) => debug!("Prefix {} is already mapped to {}", parts.join("\\"), existing.display()),
+                None => node.target = Some(target),
+            }
+        }
+        
+        root.materialise(dir.path())?;
+        Ok(Self { dir })
+    }
+    
+    /// Directory to pass as include root
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// One component of the mapped prefixes, e.g. `addons` in `z\ace\addons\main`
+#[derive(Debug, Default)]
+struct PrefixNode<'a> {
+    /// Directory mapped to the prefix ending here
+    target: Option<&'a Path>,
+    children: Vec<(&'a str, PrefixNode<'a>)>,
+}
+
+impl<'a> PrefixNode<'a> {
+    fn child(&mut self, name: &'a str) -> &mut PrefixNode<'a> {
+        let index = match self.children.iter().position(|(child, _)| child.eq_ignore_ascii_case(name)) {
+            Some(index) => index,
+            None => {
+                self.children.push((name, PrefixNode::default()));
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[index].1
+    }
+    
+    fn has_child(&self, name: &str) -> bool {
+        self.children.iter().any(|(child, _)| child.eq_ignore_ascii_case(name))
+    }
+    
+    /// Create the directory for this node at `path`
+    fn materialise(&self, path: &Path) -> io::Result<()> {
+        match self.target {
+            Some(target) if self.children.is_empty() => {
+                debug!("Mapped prefix {} to {}", path.display(), target.display());
+                return link_or_copy(target, path);
+            }
+            Some(target) => {
+                // Nested mappings shadow entries of the same name in the outer target
+                fs::create_dir_all(path)?;
+                for entry in fs::read_dir(target)? {
+                    let entry = entry?;
+                    let name = entry.file_name();
+                    if self.has_child(&name.to_string_lossy()) {
+                        debug!("{} is shadowed by a nested prefix", entry.path().display());
+                        continue;
+                    }
+                    link_or_copy(&entry.path(), &path.join(&name))?;
+                }
+                debug!("Mapped prefix {} to {}", path.display(), target.display());
+            }
+            None => fs::create_dir_all(path)?,
+        }
+        
+        for (name, child) in &self.children {
+            child.materialise(&path.join(name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Split a `\z\ace\addons\main\` style prefix into its components
+fn split_prefix(prefix: &str) -> Vec<&str> {
+    prefix.split(['\\', '/'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Link `link` to the file or directory `target`, copying it if links are unavailable
+fn link_or_copy(target: &Path, link: &Path) -> io::Result<()> {
+    if let Some(parent) = link.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let linked = if target.is_dir() { link_dir(target, link) } else { link_file(target, link) };
+    if let Err(e) = linked {
+        warn!("Failed to link {} ({}), copying includes instead; later changes to it are not seen", target.display(), e);
+        if target.is_dir() {
+            copy_includes(target, link)?;
+        } else {
+            fs::copy(target, link)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link_dir(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn link_dir(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_dir(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn link_dir(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "symlinks are not supported"))
+}
+
+#[cfg(unix)]
+fn link_file(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn link_file(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn link_file(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "symlinks are not supported"))
+}
+
+/// Recursively copy files that can be `#include`d from `source` to `dest`
+fn copy_includes(source: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        let target = dest.join(path.file_name().unwrap_or_default());
+        
+        if path.is_dir() {
+            copy_includes(&path, &target)?;
+        } else if path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| INCLUDE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .unwrap_or(false)
+        {
+            if let Err(e) = fs::copy(&path, &target) {
+                warn!("Failed to copy include {}: {}", path.display(), e);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Find the root directory of the addon containing `file_path`
+///
+/// Walks up from the file's directory and returns the first directory that
+/// contains a `$PBOPREFIX$` file or a `config.cpp`. The walk never goes above
+/// `scan_root`, so markers outside the scanned tree are not picked up. Falls
+/// back to the file's own directory when no marker is found.
+pub fn find_addon_root(file_path: &Path, scan_root: Option<&Path>) -> PathBuf {
+    let file_dir = file_path.parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    for dir in file_dir.ancestors() {
+        if ADDON_ROOT_MARKERS.iter().any(|marker| dir.join(marker).is_file()) {
+            debug!("Found addon root for {}: {}", file_path.display(), dir.display());
+            return dir.to_path_buf();
+        }
+        if scan_root.is_some_and(|root| dir == root) {
+            break;
+        }
+    }
+
+    file_dir
+}
+
+/// Path of `file_path` relative to `root`, with `/` separators as used by the workspace
+pub(crate) fn workspace_relative(root: &Path, file_path: &Path) -> Option<String> {
+    let relative = file_path.strip_prefix(root).ok()?;
+    let parts = relative.components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_addon_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let scan_root = temp_dir.path().join("mods");
+        let addon = scan_root.join("addons").join("medical");
+        fs::create_dir_all(addon.join("ui")).unwrap();
+        fs::write(addon.join("$PBOPREFIX$"), "z\\ace\\addons\\medical").unwrap();
+        fs::write(addon.join("ui").join("RscTitles.hpp"), "").unwrap();
+        let scan_root = Some(scan_root.as_path());
+
+        assert_eq!(find_addon_root(&addon.join("ui").join("RscTitles.hpp"), scan_root), addon);
+        assert_eq!(find_addon_root(&addon.join("config.cpp"), scan_root), addon);
+
+        // Markers above the scan root are ignored, the file's own directory is used
+        fs::write(temp_dir.path().join("config.cpp"), "").unwrap();
+        let loose = temp_dir.path().join("mods").join("loose").join("loose.hpp");
+        assert_eq!(find_addon_root(&loose, scan_root), temp_dir.path().join("mods").join("loose"));
+        assert_eq!(find_addon_root(&loose, None), temp_dir.path());
+    }
+
+    #[test]
+    fn test_virtual_include_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let main = temp_dir.path().join("cba_main");
+        fs::create_dir_all(&main).unwrap();
+        fs::write(main.join("script_macros_common.hpp"), "#define TEST 1").unwrap();
+        
+        let ace_main = temp_dir.path().join("ace_main");
+        fs::create_dir_all(&ace_main).unwrap();
+        fs::write(ace_main.join("script_mod.hpp"), "").unwrap();
+        
+        fs::create_dir_all(ace_main.join("Nested")).unwrap();
+        fs::write(ace_main.join("Nested").join("shadowed.hpp"), "").unwrap();
+        
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("script_nested.hpp"), "").unwrap();
+        
+        let root = VirtualIncludeRoot::new([
+            ("\\x\\cba\\addons\\main", main.as_path()),
+            ("z\\ace\\addons\\main\\", ace_main.as_path()),
+            // Inside the mapping above, but must not be created inside ace_main
+            ("z\\ace\\addons\\main\\nested", nested.as_path()),
+            // Already mapped, the first mapping wins
+            ("X\\CBA\\addons\\main", ace_main.as_path()),
+        ]).unwrap();
+        
+        assert!(root.path().join("x/cba/addons/main/script_macros_common.hpp").is_file());
+        assert!(root.path().join("z/ace/addons/main/script_mod.hpp").is_file());
+        assert!(root.path().join("z/ace/addons/main/nested/script_nested.hpp").is_file());
+        assert!(!root.path().join("z/ace/addons/main/nested/shadowed.hpp").exists());
+        assert!(!root.path().join("x/cba/addons/main/script_mod.hpp").exists());
+        assert!(!ace_main.join("nested").exists());
+        assert!(!ace_main.join("Nested").join("script_nested.hpp").exists());
+    }
+
+    #[test]
+    fn test_workspace_relative() {
+        let root = Path::new("/mods/ace/addons/medical");
+        let file = root.join("ui").join("RscTitles.hpp");
+        assert_eq!(workspace_relative(root, &file).as_deref(), Some("ui/RscTitles.hpp"));
+        assert_eq!(workspace_relative(root, Path::new("/elsewhere/file.hpp")), None);
+    }
+}
@@ -1,17 +1,32 @@
-use std::sync::Arc;
 use std::fs;
+use std::ops::Range;
 use std::path::{PathBuf, Path};
-use hemtt_config::{Config, parse, Property, Class, Value, Item};
+use hemtt_config::{Config, parse, Property, Class, Value, Item, Ident};
 use hemtt_preprocessor::Processor;
-use hemtt_workspace::{reporting::{Codes, Code, Diagnostic, Severity}, LayerType, Workspace};
+use hemtt_workspace::{reporting::{Codes, Code, Processed}, LayerType, Workspace, WorkspacePath};
 use tempfile::NamedTempFile;
 use log::{debug, trace};
-use gamedata_scanner_models::{GameClass, ClassProperty, PropertyValue, ScanResult, FileParser};
+use gamedata_scanner_models::{GameClass, ClassKind, ClassProperty, Fidelity, PropertyOperator, PropertyValue, ArrayElement, ParsedFile, ParseStatus, PreprocessorInfo, ScanResult, FileParser, SourceSpan, Diagnostic, ScanError, Severity};
 use walkdir::WalkDir;
 
 mod parser;
 pub use parser::*;
 
+mod include;
+pub use include::{ParseOptions, VirtualIncludeRoot, find_addon_root};
+
+pub mod expression;
+
+mod preprocessor_info;
+use preprocessor_info::collect_preprocessor_info;
+
+mod preprocess;
+pub use preprocess::{preprocess_file, LineOrigin, PreprocessedLine, PreprocessedOutput};
+
+mod recovery;
+pub use recovery::parse_file_recovering;
+use include::workspace_relative;
+
 // Re-export the scanner module
 pub mod scanner;
 
@@ -88,34 +103,52 @@ impl FileParser for AdvancedFileParser {
 
 pub struct CodeParser {
     config: Config,
+    /// Preprocessor output, used to map spans back to the original files
+    processed: Processed,
+    /// Workspace path of the file that was preprocessed
+    source: WorkspacePath,
+    /// Directory on disk the workspace is rooted at
+    root: PathBuf,
+    /// Include roots of the workspace, searched after `root`
+    include_roots: Vec<PathBuf>,
+    /// Byte offset of each character of the preprocessor output, `None` when
+    /// it is ASCII and hemtt's character offsets already are byte offsets
+    char_offsets: Option<Vec<usize>>,
+    /// Non-fatal issues reported by the preprocessor and the config parser
+    warnings: Vec<Diagnostic>,
 }
 
 /// Parse an HPP file and return a vector of classes.
 /// 
+/// Includes are resolved relative to the file's addon root; use
+/// [`parse_file_with_options`] to add include roots.
+/// 
 /// # Arguments
 /// 
 /// * `file_path` - Path to the HPP file to parse
 /// 
 /// # Returns
 /// 
-/// * `Result<Vec<GameClass>, Codes>` - List of classes found in the file or error
-pub fn parse_file(file_path: &std::path::Path) -> Result<Vec<GameClass>, Codes> {
+/// * `Result<Vec<GameClass>, Vec<ScanError>>` - List of classes found in the file or errors
+pub fn parse_file(file_path: &std::path::Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
+    parse_file_with_options(file_path, &ParseOptions::default())
+}
+
+/// Parse a file in place, resolving `#include`s against the real mod tree.
+/// 
+/// # Arguments
+/// 
+/// * `file_path` - Path to the file to parse
+/// * `options` - Addon root and extra include roots
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<GameClass>, Vec<ScanError>>` - List of classes found in the file or errors
+pub fn parse_file_with_options(file_path: &Path, options: &ParseOptions) -> Result<Vec<GameClass>, Vec<ScanError>> {
     debug!("\n==== PARSING FILE WITH ADVANCED PARSER: {} ====", file_path.display());
     
-    let content = match std::fs::read_to_string(file_path) {
-        Ok(content) => {
-            debug!("Successfully read file, content length: {} bytes", content.len());
-            debug!("File content preview (first 200 chars):\n{}", content.chars().take(200).collect::<String>());
-            content
-        },
-        Err(e) => {
-            debug!("Failed to read file: {}", e);
-            return Err(vec![]);
-        }
-    };
-    
-    debug!("Creating parser for file content...");
-    let parser = match CodeParser::new(&content) {
+    debug!("Creating parser for file...");
+    let parser = match CodeParser::from_file(file_path, options) {
         Ok(parser) => {
             debug!("Successfully created parser");
             parser
@@ -146,8 +179,267 @@ pub fn parse_file(file_path: &std::path::Path) -> Result<Vec<GameClass>, Codes>
     Ok(classes)
 }
 
+/// Parse a file and also record its macro definitions, macro uses and includes
+/// 
+/// # Arguments
+/// 
+/// * `file_path` - Path to the file to parse
+/// * `options` - Addon root and extra include roots
+/// 
+/// # Returns
+/// 
+/// * `Result<ParsedFile, Vec<ScanError>>` - Classes and preprocessor record, or errors
+pub fn parse_file_detailed(file_path: &Path, options: &ParseOptions) -> Result<ParsedFile, Vec<ScanError>> {
+    let parser = CodeParser::from_file(file_path, options)?;
+    let classes = parser.parse_classes(file_path);
+    let preprocessor = parser.preprocessor_info(file_path);
+    debug!("Recorded {} defines, {} macro uses and {} includes for {}",
+           preprocessor.defines.len(), preprocessor.expansions.len(),
+           preprocessor.includes.len(), file_path.display());
+    
+    Ok(ParsedFile {
+        classes,
+        preprocessor,
+        diagnostics: parser.warnings,
+        status: ParseStatus::Complete,
+        errors: Vec::new(),
+    })
+}
+
+/// Find the workspace root for a file and the file's path relative to it
+/// 
+/// The root is `options.addon_root`, or the addon root of the file when unset.
+fn locate_in_addon(file_path: &Path, options: &ParseOptions) -> Result<(PathBuf, String), Vec<ScanError>> {
+    if let Err(e) = fs::metadata(file_path) {
+        debug!("Failed to read {}: {}", file_path.display(), e);
+        return Err(vec![ScanError::io(file_path, &e)]);
+    }
+    if !file_path.is_file() {
+        debug!("Not a file: {}", file_path.display());
+        return Err(vec![ScanError::Io(Diagnostic::new(file_path, "Not a file"))]);
+    }
+    
+    // Work with absolute paths so the file can be located inside its addon root
+    let absolute = match std::path::absolute(file_path) {
+        Ok(path) => path,
+        Err(e) => {
+            debug!("Failed to resolve path {}: {}", file_path.display(), e);
+            return Err(vec![ScanError::io(file_path, &e)]);
+        }
+    };
+    
+    let root = match &options.addon_root {
+        Some(root) => std::path::absolute(root).unwrap_or_else(|_| root.clone()),
+        None => {
+            let scan_root = options.scan_root.as_deref()
+                .map(|root| std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf()));
+            find_addon_root(&absolute, scan_root.as_deref())
+        }
+    };
+    
+    match workspace_relative(&root, &absolute) {
+        Some(relative) => Ok((root, relative)),
+        None => {
+            debug!("File {} is not inside addon root {}", file_path.display(), root.display());
+            Err(vec![ScanError::Io(Diagnostic::new(
+                file_path,
+                format!("File is not inside addon root {}", root.display()),
+            ))])
+        }
+    }
+}
+
+/// Run the preprocessor on `relative` inside a workspace rooted at `root`
+/// 
+/// `file_path` is the file on disk being preprocessed, used to report errors.
+/// Files in `overlay` take precedence over the files under `root`.
+fn preprocess_in_workspace(
+    root: &Path,
+    relative: &str,
+    options: &ParseOptions,
+    file_path: &Path,
+    overlay: Option<&Path>,
+) -> Result<(Processed, WorkspacePath), Vec<ScanError>> {
+    let root = root.to_path_buf();
+    
+    // Earlier layers shadow later ones
+    let mut builder = Workspace::builder();
+    if let Some(overlay) = overlay {
+        builder = builder.physical(&overlay.to_path_buf(), LayerType::Source);
+    }
+    builder = builder.physical(&root, LayerType::Source);
+    for include_root in options.search_roots() {
+        debug!("Adding include root: {}", include_root.display());
+        builder = builder.physical(&include_root.to_path_buf(), LayerType::Include);
+    }
+    
+    let workspace = match builder.finish(None, false, &hemtt_common::config::PDriveOption::Disallow) {
+        Ok(workspace) => {
+            debug!("Successfully created workspace");
+            workspace
+        },
+        Err(e) => {
+            debug!("Failed to create workspace: {}", e);
+            return Err(vec![ScanError::Io(Diagnostic::new(
+                file_path,
+                format!("Failed to create workspace at {}: {}", root.display(), e),
+            ))]);
+        }
+    };
+        
+    let path = match workspace.join(relative) {
+        Ok(path) => {
+            debug!("Successfully joined workspace path: {:?}", path);
+            path
+        },
+        Err(e) => {
+            debug!("Failed to join workspace path: {}", e);
+            return Err(vec![ScanError::Io(Diagnostic::new(
+                file_path,
+                format!("Failed to locate {} in workspace: {}", relative, e),
+            ))]);
+        }
+    };
+    
+    match Processor::run(&path) {
+        Ok(processed) => {
+            debug!("Successfully preprocessed content");
+            Ok((processed, path))
+        },
+        Err((_, hemtt_preprocessor::Error::Code(code))) => {
+            debug!("Failed to preprocess content: {}", code.message());
+            Err(vec![scan_error(code.as_ref(), ScanError::Preprocess, None, &path, &root, file_path)])
+        },
+        Err((_, e)) => {
+            debug!("Failed to preprocess content: {}", e);
+            Err(vec![ScanError::Preprocess(Diagnostic::new(file_path, e.to_string()))])
+        }
+    }
+}
+
+/// Whether an array item has an [`ArrayElement`] counterpart
+fn is_supported_item(item: &Item) -> bool {
+    match item {
+        Item::Str(_) | Item::Number(_) | Item::Macro(_) => true,
+        Item::Array(items) => items.iter().all(is_supported_item),
+        _ => false,
+    }
+}
+
+/// Byte offsets of the characters of `text`, followed by its length
+/// 
+/// hemtt spans count characters; `None` when `text` is ASCII and the counts
+/// can be used as byte offsets directly.
+fn char_offsets(text: &str) -> Option<Vec<usize>> {
+    if text.is_ascii() {
+        return None;
+    }
+    Some(text.char_indices().map(|(offset, _)| offset).chain([text.len()]).collect())
+}
+
+/// Byte offset in `text` of the character at `chars`
+fn byte_offset(text: &str, chars: usize) -> Option<usize> {
+    text.char_indices().map(|(offset, _)| offset).chain([text.len()]).nth(chars)
+}
+
+/// Convert a hemtt code into a diagnostic
+/// 
+/// The diagnostic points at the primary label of the code, which may be in an
+/// include of `file_path`. hemtt maps labels back to the original files itself,
+/// so a label's range is a character offset into that file; its line and
+/// column are counted in the file's text, taken from `processed` when it is one
+/// of its sources and read through the workspace otherwise.
+fn diagnostic_for(
+    code: &dyn Code,
+    processed: Option<&Processed>,
+    source: &WorkspacePath,
+    root: &Path,
+    file_path: &Path,
+) -> Diagnostic {
+    let severity = match code.severity() {
+        hemtt_workspace::reporting::Severity::Error => Severity::Error,
+        hemtt_workspace::reporting::Severity::Warning => Severity::Warning,
+        hemtt_workspace::reporting::Severity::Help => Severity::Help,
+    };
+    let mut diagnostic = Diagnostic::new(file_path, code.message())
+        .with_severity(severity)
+        .with_code(code.ident());
+    
+    let labelled = code.diagnostic();
+    let Some(label) = labelled.as_ref().and_then(|labelled| labelled.labels.first()) else {
+        return diagnostic;
+    };
+    diagnostic.file = original_file(label.file(), source, root, file_path);
+    
+    let range = label.span().clone();
+    let text = processed
+        .and_then(|processed| processed.sources().into_iter().find(|(path, _)| path == label.file()))
+        .map(|(_, text)| text.to_string())
+        .or_else(|| label.file().read_to_string().ok());
+    let before = text.as_deref()
+        .and_then(|text| byte_offset(text, range.start).map(|end| &text[..end]));
+    if let Some(before) = before {
+        diagnostic.span = Some(SourceSpan {
+            file: diagnostic.file.clone(),
+            line: before.matches('\n').count() + 1,
+            column: before.rsplit('\n').next().unwrap_or_default().chars().count() + 1,
+            start: range.start,
+            end: range.end,
+        });
+    }
+    diagnostic
+}
+
+/// Convert a hemtt code into a scan error of the given kind
+fn scan_error(
+    code: &dyn Code,
+    kind: fn(Diagnostic) -> ScanError,
+    processed: Option<&Processed>,
+    source: &WorkspacePath,
+    root: &Path,
+    file_path: &Path,
+) -> ScanError {
+    kind(diagnostic_for(code, processed, source, root, file_path))
+}
+
+/// Convert hemtt codes into scan errors of the given kind
+/// 
+/// hemtt may fail without reporting a code; that still produces one error,
+/// so a failed file never ends up with an empty error list.
+fn scan_errors(
+    codes: &Codes,
+    kind: fn(Diagnostic) -> ScanError,
+    processed: Option<&Processed>,
+    source: &WorkspacePath,
+    root: &Path,
+    file_path: &Path,
+) -> Vec<ScanError> {
+    if codes.is_empty() {
+        return vec![kind(Diagnostic::new(file_path, "Failed without a diagnostic"))];
+    }
+    codes.iter()
+        .map(|code| scan_error(code.as_ref(), kind, processed, source, root, file_path))
+        .collect()
+}
+
+/// File on disk that a mapped workspace path refers to
+/// 
+/// `source` is the workspace path of the preprocessed file, which maps back to
+/// `file_path`; other files (includes) are resolved against the workspace `root`.
+pub(crate) fn original_file(original: &WorkspacePath, source: &WorkspacePath, root: &Path, file_path: &Path) -> PathBuf {
+    if original == source {
+        file_path.to_path_buf()
+    } else {
+        root.join(original.as_str().trim_start_matches('/'))
+    }
+}
+
 impl CodeParser {
-    pub fn new(content: &str) -> Result<Self, Codes> {
+    /// Create a parser for in-memory content
+    /// 
+    /// The content is written to a temporary file, so relative includes can
+    /// only resolve against the configured include roots.
+    pub fn new(content: &str) -> Result<Self, Vec<ScanError>> {
         debug!("Initializing CodeParser with content of length: {}", content.len());
         
         // Create a temporary workspace with the content
@@ -158,7 +450,7 @@ impl CodeParser {
             },
             Err(e) => {
                 debug!("Failed to create temporary file: {}", e);
-                return Err(vec![]);
+                return Err(vec![ScanError::io(&std::env::temp_dir(), &e)]);
             }
         };
         
@@ -166,56 +458,51 @@ impl CodeParser {
             Ok(_) => debug!("Wrote content to temporary file"),
             Err(e) => {
                 debug!("Failed to write content to temporary file: {}", e);
-                return Err(vec![]);
+                return Err(vec![ScanError::io(temp_file.path(), &e)]);
             }
         }
         
         let parent_path = PathBuf::from(temp_file.path().parent().unwrap());
         debug!("Using parent path: {:?}", parent_path);
         
-        let workspace = match Workspace::builder()
-            .physical(&parent_path, LayerType::Source)
-            .finish(None, false, &hemtt_common::config::PDriveOption::Disallow) {
-                Ok(workspace) => {
-                    debug!("Successfully created workspace");
-                    workspace
-                },
-                Err(e) => {
-                    debug!("Failed to create workspace: {}", e);
-                    return Err(vec![]);
-                }
-            };
-            
-        let path = match workspace.join(temp_file.path().file_name().unwrap().to_str().unwrap()) {
-            Ok(path) => {
-                debug!("Successfully joined workspace path: {:?}", path);
-                path
-            },
-            Err(e) => {
-                debug!("Failed to join workspace path: {}", e);
-                return Err(vec![]);
-            }
-        };
-        
-        let processed = match Processor::run(&path) {
-            Ok(processed) => {
-                debug!("Successfully preprocessed content");
-                processed
-            },
-            Err((_, e)) => {
-                debug!("Failed to preprocess content: {}", e);
-                #[derive(Debug)]
-                struct ProcessorError(hemtt_preprocessor::Error);
-                impl Code for ProcessorError {
-                    fn message(&self) -> String { self.0.to_string() }
-                    fn severity(&self) -> Severity { Severity::Error }
-                    fn diagnostic(&self) -> Option<Diagnostic> { None }
-                    fn ident(&self) -> &'static str { "processor_error" }
-                }
-                return Err(vec![Arc::new(ProcessorError(e))]);
-            }
-        };
-        
+        let file_name = temp_file.path().file_name().unwrap().to_str().unwrap();
+        Self::from_workspace(&parent_path, file_name, &ParseOptions::default(), temp_file.path(), None)
+    }
+    
+    /// Create a parser for a file on disk
+    /// 
+    /// The workspace is rooted at `options.addon_root`, or at the addon root
+    /// of the file when unset, so includes resolve against the real mod tree.
+    pub fn from_file(file_path: &Path, options: &ParseOptions) -> Result<Self, Vec<ScanError>> {
+        let (root, relative) = locate_in_addon(file_path, options)?;
+        Self::from_workspace(&root, &relative, options, file_path, None)
+    }
+    
+    /// Preprocess and parse `relative` inside a workspace rooted at `root`
+    /// 
+    /// `file_path` is the file on disk being parsed, used to report errors.
+    /// Files in `overlay` take precedence over the files under `root`.
+    pub(crate) fn from_workspace(
+        root: &Path,
+        relative: &str,
+        options: &ParseOptions,
+        file_path: &Path,
+        overlay: Option<&Path>,
+    ) -> Result<Self, Vec<ScanError>> {
+        let (processed, path) = preprocess_in_workspace(root, relative, options, file_path, overlay)?;
+        Self::from_processed(processed, path, root, options, file_path)
+    }
+    
+    /// Parse the output of [`preprocess_in_workspace`]
+    /// 
+    /// `path` is the workspace path of the preprocessed file `file_path`.
+    pub(crate) fn from_processed(
+        processed: Processed,
+        path: WorkspacePath,
+        root: &Path,
+        options: &ParseOptions,
+        file_path: &Path,
+    ) -> Result<Self, Vec<ScanError>> {
         let report = match parse(None, &processed) {
             Ok(report) => {
                 debug!("Successfully parsed preprocessed content");
@@ -223,14 +510,74 @@ impl CodeParser {
             },
             Err(e) => {
                 debug!("Failed to parse preprocessed content: {:?}", e);
-                return Err(e);
+                return Err(scan_errors(&e, ScanError::Parse, Some(&processed), &path, root, file_path));
             }
         };
         
-        debug!("CodeParser initialization complete");
-        Ok(Self {
+        let warnings: Vec<Diagnostic> = processed.warnings().iter()
+            .chain(report.warnings().iter())
+            .map(|code| diagnostic_for(code.as_ref(), Some(&processed), &path, root, file_path))
+            .collect();
+        debug!("Preprocessor and parser reported {} warnings", warnings.len());
+        
+        let char_offsets = char_offsets(processed.as_str());
+        let mut parser = Self {
             config: report.into_config(),
-        })
+            processed,
+            char_offsets,
+            source: path,
+            root: root.to_path_buf(),
+            include_roots: options.search_roots().into_iter().map(Path::to_path_buf).collect(),
+            warnings,
+        };
+        let unsupported = parser.unsupported_values(&parser.config.0, file_path);
+        parser.warnings.extend(unsupported);
+        
+        debug!("CodeParser initialization complete");
+        Ok(parser)
+    }
+
+    /// Warnings reported while preprocessing and parsing, e.g. unused macros
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// Macro definitions, macro uses and includes of the preprocessed file
+    /// 
+    /// Built from the preprocessor output, `file_path` is the file on disk the
+    /// parser was created for.
+    pub fn preprocessor_info(&self, file_path: &Path) -> PreprocessorInfo {
+        collect_preprocessor_info(&self.processed, &self.source, &self.root, &self.include_roots, file_path)
+    }
+
+    /// Warnings for values that can't be represented as a [`PropertyValue`]
+    /// 
+    /// Such values are kept as [`PropertyValue::Expression`] with their source
+    /// text and such array items are left out.
+    fn unsupported_values(&self, properties: &[Property], file_path: &Path) -> Vec<Diagnostic> {
+        let mut warnings = Vec::new();
+        for property in properties {
+            match property {
+                Property::Entry { name, value, .. } => {
+                    let message = match value {
+                        Value::Str(_) | Value::Number(_) | Value::Expression(_) => continue,
+                        Value::Array(arr) if arr.items.iter().all(is_supported_item) => continue,
+                        Value::Array(_) => format!("Array {} has elements that can't be represented, they are left out", name.as_str()),
+                        _ => format!("Value of {} can't be represented, it is kept as written", name.as_str()),
+                    };
+                    let span = self.span_for(name.span().start..value.span().end, file_path);
+                    let file = span.as_ref().map_or_else(|| file_path.to_path_buf(), |span| span.file.clone());
+                    let mut diagnostic = Diagnostic::new(file, message).with_severity(Severity::Warning);
+                    diagnostic.span = span;
+                    warnings.push(diagnostic);
+                }
+                Property::Class(Class::Local { properties, .. }) => {
+                    warnings.extend(self.unsupported_values(properties, file_path));
+                }
+                _ => {}
+            }
+        }
+        warnings
     }
 
     /// Parse all classes and return them as a flat list
@@ -261,27 +608,32 @@ impl CodeParser {
         debug!("First pass: Processing forward declarations and base classes");
         for (i, property) in config.0.iter().enumerate() {
             debug!("Processing property {}: {:?}", i, property);
+            if let Property::Delete(name) = property {
+                debug!("Found deletion: {}", name.as_str());
+                classes.push(self.declaration(name, ClassKind::Deleted, None, file_path));
+            }
             if let Property::Class(class) = property {
                 match class {
                     Class::External { name, .. } => {
                         debug!("Found forward declaration: {}", name.as_str());
                         if !classes.iter().any(|c| c.name == name.as_str()) {
                             debug!("Adding forward declaration for class: {}", name.as_str());
-                            classes.push(GameClass {
-                                name: name.as_str().to_string(),
-                                parent: None,
-                                properties: Vec::new(),
-                                container_class: None,
-                                file_path: file_path.to_path_buf(),
-                            });
+                            classes.push(self.declaration(name, ClassKind::External, None, file_path));
                         }
                     },
                     Class::Local { name, parent, properties, .. } => {
                         // Handle base class definitions (classes without inheritance)
                         if parent.is_none() {
                             debug!("Found base class: {}", name.as_str());
-                            let class_def = self.create_class(name.as_str(), None, properties, classes, false, file_path);
-                            if !classes.iter().any(|c| c.name == name.as_str()) {
+                            let class_def = self.create_class(name, None, properties, classes, false, file_path);
+                            let declared = classes.iter().position(|c| {
+                                c.name == name.as_str() && c.container_class.is_none() && c.kind == ClassKind::External
+                            });
+                            if let Some(idx) = declared {
+                                // A definition after `class Foo;` replaces the declaration
+                                debug!("Defining declared class: {}", name.as_str());
+                                classes[idx] = class_def;
+                            } else if !classes.iter().any(|c| c.name == name.as_str()) {
                                 debug!("Adding base class: {}", name.as_str());
                                 classes.push(class_def);
                             }
@@ -313,7 +665,7 @@ impl CodeParser {
                             debug!("Processing class definition: {} (parent: {:?})", name.as_str(), parent.as_ref().map(|p| p.as_str()));
                             
                             // Create the class with its full definition
-                            let class_def = self.create_class(name.as_str(), parent.as_ref().map(|p| p.as_str()), properties, classes, false, file_path);
+                            let class_def = self.create_class(name, parent.as_ref().map(|p| p.as_str()), properties, classes, false, file_path);
                             
                             // Update or add the class definition
                             if let Some(idx) = classes.iter().position(|c| c.name == name.as_str()) {
@@ -337,7 +689,7 @@ impl CodeParser {
                                     
                                     // Create and add the nested class with its hierarchical name
                                     let mut nested_def = self.create_class(
-                                        nested_name.as_str(),
+                                        nested_name,
                                         nested_parent.as_ref().map(|p| p.as_str()),
                                         nested_props,
                                         classes,
@@ -379,6 +731,9 @@ impl CodeParser {
                                     properties: Vec::new(),
                                     container_class: None,
                                     file_path: file_path.to_path_buf(),
+                                    span: self.ident_span(name, file_path),
+                                    kind: ClassKind::Definition,
+                                    fidelity: Fidelity::Full,
                                 };
                                 
                                 // Process properties and nested classes
@@ -415,13 +770,16 @@ impl CodeParser {
     }
     
     /// Create a class from its name, parent, and properties
-    fn create_class(&self, name: &str, parent: Option<&str>, properties: &[Property], classes: &mut Vec<GameClass>, add_to_classes: bool, file_path: &std::path::Path) -> GameClass {
+    fn create_class(&self, name: &Ident, parent: Option<&str>, properties: &[Property], classes: &mut Vec<GameClass>, add_to_classes: bool, file_path: &std::path::Path) -> GameClass {
         let mut game_class = GameClass {
-            name: name.to_string(),
+            name: name.as_str().to_string(),
             parent: parent.map(|p| p.to_string()),
             properties: Vec::new(),
             container_class: None,
             file_path: file_path.to_path_buf(),
+            span: self.ident_span(name, file_path),
+            kind: ClassKind::Definition,
+            fidelity: Fidelity::Full,
         };
         
         // Process properties
@@ -430,7 +788,7 @@ impl CodeParser {
         // If we're instructed to add it to classes, do so
         if add_to_classes {
             // Check if we already have this class
-            if let Some(idx) = classes.iter().position(|c| c.name == name && c.container_class == game_class.container_class) {
+            if let Some(idx) = classes.iter().position(|c| c.name == name.as_str() && c.container_class == game_class.container_class) {
                 // Update existing class
                 classes[idx] = game_class.clone();
             } else {
@@ -448,9 +806,16 @@ impl CodeParser {
             match prop {
                 Property::Entry { name, value, .. } => {
                     trace!("  Adding property: {}", name.as_str());
+                    // `name[] += {...}` is parsed as an array marked for expansion
+                    let operator = match value {
+                        Value::Array(arr) if arr.expand => PropertyOperator::Append,
+                        _ => PropertyOperator::Assign,
+                    };
                     class.properties.push(ClassProperty {
                         name: name.as_str().to_string(),
-                        value: self.convert_value(value),
+                        value: self.convert_value(value, &class.properties),
+                        span: self.span_for(name.span().start..value.span().end, file_path),
+                        operator,
                     });
                 },
                 Property::Class(nested_class) => {
@@ -464,6 +829,9 @@ impl CodeParser {
                             properties: Vec::new(),
                             container_class: Some(class.name.clone()),
                             file_path: file_path.to_path_buf(),
+                            span: self.ident_span(name, file_path),
+                            kind: ClassKind::Definition,
+                            fidelity: Fidelity::Full,
                         };
                         
                         // Process properties of the nested class
@@ -473,6 +841,8 @@ impl CodeParser {
                         class.properties.push(ClassProperty {
                             name: name.as_str().to_string(),
                             value: PropertyValue::Class(Box::new(nested.clone())),
+                            span: nested.span.clone(),
+                            operator: PropertyOperator::Assign,
                         });
                         
                         // Also add the nested class to the classes list with its container info
@@ -481,80 +851,150 @@ impl CodeParser {
                             debug!("  Adding nested class to main class list: {} (container: {})", name.as_str(), class.name);
                             classes.push(nested);
                         }
+                    } else if let Class::External { name, .. } = nested_class {
+                        trace!("  Adding external class: {}", name.as_str());
+                        let external = self.declaration(name, ClassKind::External, Some(&class.name), file_path);
+                        class.properties.push(ClassProperty {
+                            name: name.as_str().to_string(),
+                            span: external.span.clone(),
+                            value: PropertyValue::Class(Box::new(external)),
+                            operator: PropertyOperator::Assign,
+                        });
                     }
                 },
+                Property::Delete(name) => {
+                    trace!("  Adding deletion: {}", name.as_str());
+                    let deleted = self.declaration(name, ClassKind::Deleted, Some(&class.name), file_path);
+                    class.properties.push(ClassProperty {
+                        name: name.as_str().to_string(),
+                        span: deleted.span.clone(),
+                        value: PropertyValue::Class(Box::new(deleted)),
+                        operator: PropertyOperator::Assign,
+                    });
+                },
                 _ => {}
             }
         }
     }
+    
+    /// Create a class for a statement without a body (`class Foo;` or `delete Foo;`)
+    fn declaration(&self, name: &Ident, kind: ClassKind, container: Option<&str>, file_path: &Path) -> GameClass {
+        GameClass {
+            name: name.as_str().to_string(),
+            parent: None,
+            properties: Vec::new(),
+            container_class: container.map(str::to_string),
+            file_path: file_path.to_path_buf(),
+            span: self.ident_span(name, file_path),
+            kind,
+            fidelity: Fidelity::Full,
+        }
+    }
 
-    fn convert_value(&self, value: &Value) -> PropertyValue {
+    /// Source span of an identifier (class or property name)
+    fn ident_span(&self, ident: &Ident, file_path: &Path) -> Option<SourceSpan> {
+        self.span_for(ident.span().clone(), file_path)
+    }
+    
+    /// Map a character range of the preprocessed output back to the file it came from
+    /// 
+    /// Ranges inside the parsed file are reported against `file_path`; ranges that
+    /// came in through an `#include` are reported against the included file on disk.
+    fn span_for(&self, range: Range<usize>, file_path: &Path) -> Option<SourceSpan> {
+        let original = self.processed.mapping(range.start)?.original();
+        
+        let file = original_file(original.path(), &self.source, &self.root, file_path);
+        
+        // Prefer the mapped end when the range ends in the same file, otherwise
+        // fall back to the length of the expanded text
+        let start = original.start().0;
+        let end = self.processed.mapping(range.end.saturating_sub(1))
+            .map(|m| m.original())
+            .filter(|end| end.path() == original.path() && end.end().0 >= start)
+            .map(|end| end.end().0)
+            .unwrap_or(start + range.len());
+        
+        // hemtt reports 1-based lines and 0-based columns
+        let (line, column) = original.start().1;
+        Some(SourceSpan {
+            file,
+            line,
+            column: column + 1,
+            start,
+            end,
+        })
+    }
+
+    /// Convert a config value; `scope` holds the properties declared before it in the same class
+    fn convert_value(&self, value: &Value, scope: &[ClassProperty]) -> PropertyValue {
         match value {
             Value::Str(s) => PropertyValue::String(s.value().to_string()),
             Value::Number(n) => {
                 match n {
                     hemtt_config::Number::Int32 { value, .. } => PropertyValue::Number(*value as i64),
                     hemtt_config::Number::Int64 { value, .. } => PropertyValue::Number(*value),
-                    hemtt_config::Number::Float32 { value, .. } => PropertyValue::Number(*value as i64),
+                    hemtt_config::Number::Float32 { value, .. } => PropertyValue::from(*value),
                 }
             }
             Value::Array(arr) => {
-                let mut values = Vec::new();
-                for item in &arr.items {
-                    match item {
-                        Item::Str(s) => values.push(s.value().to_string()),
-                        Item::Number(n) => values.push(n.to_string()),
-                        // Handle macros using the correct struct pattern
-                        Item::Macro(macro_expr) => {
-                            let macro_name = macro_expr.name().value();
-                            let args = macro_expr.args();
-                            
-                            // Enhanced argument handling with better formatting
-                            let args_str = args.iter()
-                                .map(|arg| {
-                                    // Properly quote string arguments if they contain spaces or special chars
-                                    let arg_value = arg.value().to_string();
-                                    if arg_value.contains(' ') || arg_value.contains(',') || 
-                                       arg_value.contains('(') || arg_value.contains(')') {
-                                        format!("\"{}\"", arg_value.replace('"', "\\\""))
-                                    } else {
-                                        arg_value
-                                    }
-                                })
-                                .collect::<Vec<_>>()
-                                .join(", ");
-                            
-                            // Special handling for different types of macros
-                            let macro_value = if macro_name.starts_with("ARR_") {
-                                // For ARR_ macros, wrap the arguments in curly braces
-                                format!("{{{}}}", args_str)
-                            } else if macro_name.starts_with("LIST_") {
-                                // For LIST_ macros, preserve the exact format
-                                format!("{}({})", macro_name, args_str)
-                            } else if macro_name.starts_with("CONCAT_") ||
-                                      macro_name.starts_with("QUOTE") ||
-                                      macro_name.starts_with("QGVAR") ||
-                                      macro_name.starts_with("QQGVAR") ||
-                                      macro_name.starts_with("DOUBLES") {
-                                // Special formatting for common Arma macros
-                                format!("{}({})", macro_name, args_str)
-                            } else if !args.is_empty() {
-                                // Generic formatting for other macros with arguments
-                                format!("{}({})", macro_name, args_str)
-                            } else {
-                                // Macros without arguments
-                                macro_name.to_string()
-                            };
-                            
-                            values.push(macro_value);
-                        }
-                        _ => values.push("Unknown".to_string()),
-                    }
-                }
-                PropertyValue::Array(values)
+                PropertyValue::Array(arr.items.iter().filter_map(|item| self.convert_item(item)).collect())
+            }
+            Value::Expression(_) => {
+                let source = self.source_text(value);
+                // Identifiers may refer to numeric properties declared earlier in the class
+                let evaluated = expression::evaluate(&source, |name| {
+                    scope.iter()
+                        .rev()
+                        .find(|p| p.name.eq_ignore_ascii_case(name))
+                        .and_then(|p| p.value.as_f64())
+                });
+                PropertyValue::Expression { source, evaluated }
+            }
+            // Anything else is kept as written rather than guessed at, with a
+            // warning from `unsupported_values`
+            _ => PropertyValue::Expression {
+                source: self.source_text(value),
+                evaluated: None,
+            },
+        }
+    }
+
+    /// Preprocessed text of a value, as written
+    fn source_text(&self, value: &Value) -> String {
+        let span = value.span();
+        let range = match &self.char_offsets {
+            Some(offsets) => offsets.get(span.start).zip(offsets.get(span.end)).map(|(&start, &end)| start..end),
+            None => Some(span.start..span.end),
+        };
+        range.and_then(|range| self.processed.as_str().get(range))
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    }
+
+    /// Convert a single array item, recursing into nested arrays
+    /// 
+    /// Items with no [`ArrayElement`] counterpart give `None` and are left out,
+    /// with a warning from `unsupported_values`.
+    fn convert_item(&self, item: &Item) -> Option<ArrayElement> {
+        match item {
+            Item::Str(s) => Some(ArrayElement::String(s.value().to_string())),
+            Item::Number(n) => Some(match n {
+                hemtt_config::Number::Int32 { value, .. } => ArrayElement::Number(*value as i64),
+                hemtt_config::Number::Int64 { value, .. } => ArrayElement::Number(*value),
+                hemtt_config::Number::Float32 { value, .. } => ArrayElement::from(*value),
+            }),
+            Item::Array(items) => {
+                Some(ArrayElement::Array(items.iter().filter_map(|nested| self.convert_item(nested)).collect()))
             }
-            Value::Expression(_) => PropertyValue::String("Expression".to_string()),
-            _ => PropertyValue::String(String::new()),
+            // Macros the preprocessor left alone (e.g. LIST_2(...)) are kept as calls
+            Item::Macro(macro_expr) => Some(ArrayElement::Macro {
+                name: macro_expr.name().value().to_string(),
+                args: macro_expr.args().iter()
+                    .map(|arg| arg.value().to_string())
+                    .collect(),
+            }),
+            _ => None,
         }
     }
 }
@@ -622,10 +1062,10 @@ mod tests {
         
         let uniform_prop = test_class.properties.iter().find(|p| p.name == "uniform").unwrap();
         if let PropertyValue::Array(uniforms) = &uniform_prop.value {
-            // Check for the specific format of the LIST_2 macro
-            assert!(uniforms.iter().any(|u| u.contains("LIST_2")));
-            assert!(uniforms.contains(&"usp_g3c_rs_kp_mx_aor2".to_string()));
-            assert!(uniforms.contains(&"usp_g3c_rs2_kp_mx_aor2".to_string()));
+            // The LIST_2 macro is kept as an unexpanded macro call
+            assert!(uniforms.iter().any(|u| u.macro_name() == Some("LIST_2")));
+            assert!(uniforms.iter().any(|u| u.as_string() == Some("usp_g3c_rs_kp_mx_aor2")));
+            assert!(uniforms.iter().any(|u| u.as_string() == Some("usp_g3c_rs2_kp_mx_aor2")));
             assert_eq!(uniforms.len(), 3); // Should have 3 items now - the macro and two strings
         } else {
             panic!("Expected uniform to be an array");
@@ -657,13 +1097,14 @@ mod tests {
             assert_eq!(weapons.len(), 3);
             
             // String item
-            assert!(weapons.contains(&"standard_rifle".to_string()));
+            assert!(weapons.iter().any(|w| w.as_string() == Some("standard_rifle")));
             
             // Check for CONCAT_3 macro
-            assert!(weapons.iter().any(|w| w.contains("CONCAT_3")));
+            assert!(weapons.iter().any(|w| w.macro_name() == Some("CONCAT_3")));
             
-            // Check for CUSTOM_MACRO
-            assert!(weapons.iter().any(|w| w.contains("CUSTOM_MACRO")));
+            // Check for CUSTOM_MACRO and its arguments
+            assert!(weapons.iter().any(|w| matches!(w,
+                ArrayElement::Macro { name, args } if name == "CUSTOM_MACRO" && args.len() == 3)));
         } else {
             panic!("Expected weapons to be an array");
         }
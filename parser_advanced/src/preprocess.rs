@@ -0,0 +1,128 @@
+//! Preprocess-only mode
+//!
+//! Runs the preprocessor without parsing, so the expanded text of a file that
+//! fails to parse can be inspected, with each line traced back to its origin.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use gamedata_scanner_models::ScanError;
+use log::debug;
+
+use crate::{locate_in_addon, original_file, preprocess_in_workspace, ParseOptions};
+
+/// Where a line of preprocessed output came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineOrigin {
+    /// File containing the original line
+    pub file: PathBuf,
+    /// Line number in that file (1-based)
+    pub line: usize,
+}
+
+/// A single line of preprocessed output
+#[derive(Debug, Clone)]
+pub struct PreprocessedLine {
+    /// Expanded text
+    pub text: String,
+    /// Origin of the first token on the line, `None` for blank lines
+    pub origin: Option<LineOrigin>,
+}
+
+/// The fully expanded text of a file
+#[derive(Debug, Clone)]
+pub struct PreprocessedOutput {
+    /// File that was preprocessed
+    pub file: PathBuf,
+    /// Expanded lines, in output order
+    pub lines: Vec<PreprocessedLine>,
+}
+
+impl PreprocessedOutput {
+    /// The expanded text, exactly as the config parser sees it
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            text.push_str(&line.text);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The expanded text with `#line`-style origin comments
+    ///
+    /// A `// #line <n> "<file>"` comment is written whenever the output stops
+    /// following the previous line of the same file, e.g. at the start of an
+    /// include or after a multi-line macro expansion. Blank lines are dropped.
+    pub fn annotated(&self) -> String {
+        let mut text = String::new();
+        let mut expected: Option<LineOrigin> = None;
+
+        for line in &self.lines {
+            let Some(origin) = &line.origin else {
+                continue;
+            };
+            if expected.as_ref() != Some(origin) {
+                let _ = writeln!(text, "// #line {} \"{}\"", origin.line, origin.file.display());
+            }
+            text.push_str(&line.text);
+            text.push('\n');
+
+            expected = Some(LineOrigin {
+                file: origin.file.clone(),
+                line: origin.line + 1,
+            });
+        }
+
+        text
+    }
+}
+
+/// Run only the preprocessor on a file, resolving includes like [`crate::parse_file_with_options`]
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file to preprocess
+/// * `options` - Addon root and extra include roots
+///
+/// # Returns
+///
+/// * `Result<PreprocessedOutput, Vec<ScanError>>` - The expanded text or preprocessor errors
+pub fn preprocess_file(file_path: &Path, options: &ParseOptions) -> Result<PreprocessedOutput, Vec<ScanError>> {
+    debug!("Preprocessing {}", file_path.display());
+    let (root, relative) = locate_in_addon(file_path, options)?;
+    let (processed, source) = preprocess_in_workspace(&root, &relative, options, file_path, None)?;
+
+    // Output positions are counted in characters, like the spans of the config parser
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for text in processed.as_str().split('\n') {
+        let indent = text.len() - text.trim_start().len();
+        let origin = if text.trim().is_empty() {
+            None
+        } else {
+            processed.mapping(offset + indent).map(|mapping| {
+                let original = mapping.original();
+                LineOrigin {
+                    file: original_file(original.path(), &source, &root, file_path),
+                    line: original.start().1.0,
+                }
+            })
+        };
+
+        lines.push(PreprocessedLine {
+            text: text.trim_end_matches('\r').to_string(),
+            origin,
+        });
+        offset += text.chars().count() + 1;
+    }
+
+    // A trailing newline produces an empty last line
+    if lines.last().is_some_and(|line| line.text.is_empty()) {
+        lines.pop();
+    }
+
+    Ok(PreprocessedOutput {
+        file: file_path.to_path_buf(),
+        lines,
+    })
+}
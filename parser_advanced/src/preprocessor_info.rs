@@ -0,0 +1,432 @@
+//! Builds the record of macro definitions, macro uses and includes of a file
+//!
+//! Everything comes from hemtt's [`Processed`] output, so only what the
+//! preprocessor actually did is recorded: definitions in branches skipped by
+//! `#if`/`#ifdef` are left out, and a macro used in the body of another one is
+//! reported where it is written in that body. [`Processed`] doesn't keep the
+//! `#include` directives, so they are read from the processed sources it holds,
+//! following the includes from the parsed file and skipping the branches of
+//! `#if`/`#ifdef`/`#ifndef` that weren't taken.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use log::trace;
+use hemtt_workspace::reporting::{Definition, Processed, Token};
+use hemtt_workspace::WorkspacePath;
+use gamedata_scanner_models::{IncludeRecord, MacroDefinition, MacroExpansion, PreprocessorInfo};
+
+/// Record the preprocessor activity of a processed file
+///
+/// `source` is the workspace path of `file_path`, the file that was
+/// preprocessed; other processed files are looked up in the workspace `root`
+/// and then the `include_roots`, in the order of the workspace layers.
+pub(crate) fn collect_preprocessor_info(
+    processed: &Processed,
+    source: &WorkspacePath,
+    root: &Path,
+    include_roots: &[PathBuf],
+    file_path: &Path,
+) -> PreprocessorInfo {
+    let disk_path = |path: &WorkspacePath| {
+        if path == source {
+            return file_path.to_path_buf();
+        }
+        let relative = path.as_str().trim_start_matches('/');
+        std::iter::once(root)
+            .chain(include_roots.iter().map(PathBuf::as_path))
+            .map(|root| root.join(relative))
+            .find(|candidate| candidate.is_file())
+            .unwrap_or_else(|| root.join(relative))
+    };
+    let sources = processed.sources();
+    let files: Vec<PathBuf> = sources.iter().map(|(path, _)| disk_path(path)).collect();
+    let source_index = |path: &WorkspacePath| sources.iter().position(|(source, _)| source == path);
+    let file_of = |path: &WorkspacePath| match source_index(path) {
+        Some(index) => files[index].clone(),
+        None => disk_path(path),
+    };
+
+    // Includes, and the file and line each source was first included from
+    let mut walk = IncludeWalk {
+        sources: &sources,
+        defined: HashMap::new(),
+        walking: Vec::new(),
+        seen: HashSet::new(),
+        includes: Vec::new(),
+        included_from: vec![None; sources.len()],
+    };
+    if let Some(index) = source_index(source) {
+        walk.walk(index);
+    }
+    let IncludeWalk { includes, included_from, .. } = walk;
+
+    // Position in the order the preprocessor reached it: the lines of the
+    // includes leading to the file, then the line and offset in the file
+    let order = |index: Option<usize>, line: usize, offset: usize| {
+        let mut key = vec![(line, offset)];
+        let mut current = index;
+        while let Some((parent, line)) = current.and_then(|index| included_from[index]) {
+            if key.len() > sources.len() {
+                break;
+            }
+            key.push((line, usize::MAX));
+            current = Some(parent);
+        }
+        key.reverse();
+        key
+    };
+
+    let mut defines = Vec::new();
+    let mut expansions = Vec::new();
+    for (name, definitions) in processed.macros() {
+        for (position, definition) in definitions {
+            let (params, body) = match definition {
+                Definition::Function(function) => (
+                    Some(function.args().iter().map(|arg| arg.to_source()).collect()),
+                    render(function.body()),
+                ),
+                Definition::Value(body) => (None, render(body.iter())),
+                _ => (None, String::new()),
+            };
+            let line = position.start().1.0;
+            defines.push((order(source_index(position.path()), line, position.start().0), MacroDefinition {
+                name: name.clone(),
+                params,
+                body,
+                file: file_of(position.path()),
+                line,
+            }));
+
+            for used in processed.usage().get(position).into_iter().flatten() {
+                let line = used.start().1.0;
+                expansions.push((order(source_index(used.path()), line, used.start().0), MacroExpansion {
+                    name: name.clone(),
+                    file: file_of(used.path()),
+                    line,
+                }));
+            }
+        }
+    }
+    defines.sort_by(|(a, _), (b, _)| a.cmp(b));
+    expansions.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    PreprocessorInfo {
+        defines: defines.into_iter().map(|(_, define)| define).collect(),
+        expansions: expansions.into_iter().map(|(_, expansion)| expansion).collect(),
+        includes: includes.into_iter()
+            .map(|(index, line, path, resolved)| IncludeRecord {
+                path,
+                resolved: resolved.map(|resolved| files[resolved].clone()),
+                file: files[index].clone(),
+                line,
+            })
+            .collect(),
+    }
+}
+
+/// Deepest include nesting followed, like the preprocessor's own limit
+const MAX_INCLUDE_DEPTH: usize = 64;
+
+/// Reads the `#include` directives of the processed sources in the order the
+/// preprocessor reached them
+///
+/// Conditions are evaluated against the macros defined so far: `#ifdef` and
+/// `#ifndef` check a name, `#if` takes a number or a macro whose value is a
+/// number, and anything else counts as false.
+struct IncludeWalk<'a, S> {
+    sources: &'a [(WorkspacePath, S)],
+    /// Macros defined so far, with their values
+    defined: HashMap<String, String>,
+    /// Sources being read, innermost last
+    walking: Vec<usize>,
+    /// Directives already recorded, a file included twice lists its includes once
+    seen: HashSet<(usize, usize)>,
+    /// Including source, line, path as written and the source it names
+    includes: Vec<(usize, usize, String, Option<usize>)>,
+    /// Source and line each source was first included from
+    included_from: Vec<Option<(usize, usize)>>,
+}
+
+impl<S: AsRef<str>> IncludeWalk<'_, S> {
+    fn walk(&mut self, index: usize) {
+        if self.walking.contains(&index) || self.walking.len() >= MAX_INCLUDE_DEPTH {
+            return;
+        }
+        self.walking.push(index);
+
+        let sources = self.sources;
+        let (path, text) = &sources[index];
+        // For each open conditional: whether the current branch is read, and
+        // whether any branch of it was
+        let mut branches: Vec<(bool, bool)> = Vec::new();
+        for (line, text) in logical_lines(&strip_comments(text.as_ref())) {
+            let Some(directive) = text.trim_start().strip_prefix('#') else {
+                continue;
+            };
+            let (keyword, rest) = split_ident(directive.trim_start());
+            let active = branches.iter().all(|&(read, _)| read);
+            match keyword {
+                "ifdef" | "ifndef" | "if" => {
+                    let taken = active && self.condition(keyword, rest);
+                    branches.push((taken, taken));
+                }
+                "else" => {
+                    if let Some((read, taken)) = branches.pop() {
+                        let outer = branches.iter().all(|&(read, _)| read);
+                        branches.push((outer && !taken, taken || read));
+                    }
+                }
+                "endif" => {
+                    branches.pop();
+                }
+                _ if !active => {}
+                "define" => {
+                    let (name, body) = split_ident(rest.trim_start());
+                    self.defined.insert(name.to_string(), body.trim().to_string());
+                }
+                "undef" => {
+                    self.defined.remove(split_ident(rest.trim_start()).0);
+                }
+                "include" => {
+                    let Some(included) = include_directive(&text) else {
+                        continue;
+                    };
+                    let resolved = resolve_include(included, path, sources);
+                    if self.seen.insert((index, line)) {
+                        if resolved.is_none() {
+                            trace!("Include {} of {} was not processed", included, path.as_str());
+                        }
+                        self.includes.push((index, line, included.to_string(), resolved));
+                    }
+                    if let Some(resolved) = resolved {
+                        if self.included_from[resolved].is_none() && !self.walking.contains(&resolved) {
+                            self.included_from[resolved] = Some((index, line));
+                        }
+                        self.walk(resolved);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.walking.pop();
+    }
+
+    /// Whether the condition of an `#ifdef`, `#ifndef` or `#if` holds
+    fn condition(&self, keyword: &str, rest: &str) -> bool {
+        let rest = rest.trim();
+        match keyword {
+            "ifdef" => self.defined.contains_key(split_ident(rest).0),
+            "ifndef" => !self.defined.contains_key(split_ident(rest).0),
+            _ => {
+                let value = self.defined.get(rest).map_or(rest, String::as_str);
+                value.parse::<f64>().is_ok_and(|number| number != 0.0)
+            }
+        }
+    }
+}
+
+/// Source text of a macro body, with whitespace and line continuations collapsed
+fn render<'a>(tokens: impl IntoIterator<Item = &'a std::sync::Arc<Token>>) -> String {
+    let text: String = tokens.into_iter().map(|token| token.to_source()).collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The path of an `#include "..."` or `#include <...>` line
+fn include_directive(line: &str) -> Option<&str> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let (keyword, rest) = split_ident(directive);
+    if keyword != "include" {
+        return None;
+    }
+    let rest = rest.trim();
+    rest.strip_prefix('"')
+        .and_then(|r| r.split_once('"'))
+        .or_else(|| rest.strip_prefix('<').and_then(|r| r.split_once('>')))
+        .map(|(path, _)| path)
+}
+
+/// Index of the processed source an include refers to
+///
+/// Paths starting with `\` are absolute in the workspace, other paths are
+/// relative to the including file. Like the game, matching ignores case.
+fn resolve_include<S>(included: &str, including: &WorkspacePath, sources: &[(WorkspacePath, S)]) -> Option<usize> {
+    let normalized = included.replace('\\', "/");
+    let joined = match normalized.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => {
+            let including = including.as_str().trim_start_matches('/');
+            match including.rsplit_once('/') {
+                Some((dir, _)) => format!("{}/{}", dir, normalized),
+                None => normalized,
+            }
+        }
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    let wanted = parts.join("/");
+
+    sources.iter().position(|(path, _)| path.as_str().trim_start_matches('/').eq_ignore_ascii_case(&wanted))
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+/// Split a leading identifier off `text`
+fn split_ident(text: &str) -> (&str, &str) {
+    if !text.starts_with(is_ident_start) {
+        return ("", text);
+    }
+    let end = text.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(text.len());
+    text.split_at(end)
+}
+
+/// Replace comments with spaces, keeping line breaks so line numbers stay valid
+fn strip_comments(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                result.push(c);
+            }
+            '\n' => {
+                // Strings can't span lines; don't let a stray quote swallow the file
+                in_string = false;
+                result.push(c);
+            }
+            '/' if !in_string && chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&next| next != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if !in_string && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        result.push('\n');
+                    }
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                result.push(' ');
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Split content into lines, joining `\` continuations, with 1-based line numbers
+fn logical_lines(content: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let (start, mut text) = current.take().unwrap_or((index + 1, String::new()));
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                text.push_str(continued);
+                text.push(' ');
+                current = Some((start, text));
+            }
+            None => {
+                text.push_str(line);
+                lines.push((start, text));
+            }
+        }
+    }
+    if let Some(last) = current {
+        lines.push(last);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::{CodeParser, ParseOptions};
+
+    #[test]
+    fn test_collect_preprocessor_info() {
+        let temp_dir = TempDir::new().unwrap();
+        let cba = temp_dir.path().join("pdrive").join("x").join("cba").join("addons").join("main");
+        fs::create_dir_all(&cba).unwrap();
+        fs::write(cba.join("script_macros_common.hpp"), "#define QUOTE(var1) #var1\n").unwrap();
+
+        let addon = temp_dir.path().join("addon");
+        fs::create_dir_all(&addon).unwrap();
+        fs::write(addon.join("script_component.hpp"), concat!(
+            "#include \"\\x\\cba\\addons\\main\\script_macros_common.hpp\"\n",
+            "#ifdef DISABLED\n",
+            "#include \"\\x\\cba\\addons\\missing.hpp\"\n",
+            "#include \"\\x\\cba\\addons\\main\\script_macros_common.hpp\"\n",
+            "#define HIDDEN 1\n",
+            "#endif\n",
+            "#define GVAR(var1) \\\n    ace_##var1\n",
+        )).unwrap();
+        fs::write(addon.join("config.cpp"), concat!(
+            "#include \"script_component.hpp\"\n",
+            "#define MASS 10 // grams\n",
+            "#define MASSES {MASS, MASS}\n",
+            "class CfgWeapons {\n",
+            "    displayName = \"GVAR(in_string)\";\n",
+            "    mass = MASS;\n",
+            "    masses[] = MASSES;\n",
+            "    script = QUOTE(GVAR(fnc));\n",
+            "};\n",
+        )).unwrap();
+
+        let options = ParseOptions::new()
+            .with_addon_root(&addon)
+            .with_include_root(temp_dir.path().join("pdrive"));
+        let config = addon.join("config.cpp");
+        let info = CodeParser::from_file(&config, &options).unwrap().preprocessor_info(&config);
+
+        // Definitions in skipped branches are not recorded
+        let names: Vec<&str> = info.defines.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["QUOTE", "GVAR", "MASS", "MASSES"]);
+
+        let gvar = info.definition("GVAR").unwrap();
+        assert_eq!(gvar.params.as_deref(), Some(&["var1".to_string()][..]));
+        assert_eq!(gvar.body, "ace_##var1");
+        assert_eq!(gvar.file, addon.join("script_component.hpp"));
+        assert_eq!(gvar.line, 7);
+        assert_eq!(info.definition("MASS").unwrap().body, "10");
+
+        // Uses in strings are not expansions, uses in macro bodies are reported in the body
+        let lines = |name| info.expansions_of(name).map(|e| e.line).collect::<Vec<_>>();
+        assert!(lines("MASS").contains(&3));
+        assert!(lines("MASS").contains(&6));
+        assert_eq!(lines("MASSES"), vec![7]);
+        assert_eq!(lines("GVAR"), vec![8]);
+
+        // Includes in skipped branches are not recorded, even of files included elsewhere
+        let includes: Vec<(&str, usize)> = info.includes.iter().map(|i| (i.path.as_str(), i.line)).collect();
+        assert_eq!(includes, vec![
+            ("script_component.hpp", 1),
+            ("\\x\\cba\\addons\\main\\script_macros_common.hpp", 1),
+        ]);
+        assert_eq!(info.includes[1].resolved, Some(cba.join("script_macros_common.hpp")));
+        assert_eq!(info.missing_includes().count(), 0);
+    }
+}
@@ -0,0 +1,442 @@
+//! Error-recovering parse that keeps the classes around a syntax error
+//!
+//! hemtt's config parser fails the whole file on the first syntax error. When
+//! that happens, the original source is split into class statements, and
+//! groups of them are parsed on their own with the rest of the file blanked out
+//! (a space per character, keeping line breaks, so hemtt's character offsets
+//! and lines stay valid). Groups
+//! that fail are halved until the broken classes are isolated; a class whose
+//! nested classes are broken is kept without them.
+//!
+//! The blanked copy is put in a workspace layer above the addon, so includes
+//! resolve exactly as for the original file.
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use log::debug;
+use tempfile::TempDir;
+use gamedata_scanner_models::{ClassProperty, Diagnostic, GameClass, ParseStatus, ParsedFile, PropertyValue, ScanError};
+
+use crate::{collect_preprocessor_info, locate_in_addon, preprocess_in_workspace, CodeParser, ParseOptions};
+
+/// Parse a file, recovering the classes that parse on their own if the file has syntax errors
+///
+/// Files that parse are returned as [`ParseStatus::Complete`]. Files with syntax
+/// errors are returned as [`ParseStatus::Partial`] with the errors of the classes
+/// that had to be dropped, unless no class could be recovered. IO and
+/// preprocessor errors affect the whole file and are returned as errors.
+///
+/// The file is preprocessed once, for its preprocessor record too. Each
+/// recovery attempt preprocesses a blanked copy again, so a file with syntax
+/// errors costs several preprocessor runs instead of one.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file to parse
+/// * `options` - Addon root and extra include roots
+///
+/// # Returns
+///
+/// * `Result<ParsedFile, Vec<ScanError>>` - Classes and preprocessor record, or errors
+pub fn parse_file_recovering(file_path: &Path, options: &ParseOptions) -> Result<ParsedFile, Vec<ScanError>> {
+    let (root, relative) = locate_in_addon(file_path, options)?;
+    let (processed, source) = preprocess_in_workspace(&root, &relative, options, file_path, None)?;
+
+    // The record covers the whole file, whichever of its classes parse
+    let include_roots: Vec<PathBuf> = options.search_roots().into_iter().map(Path::to_path_buf).collect();
+    let preprocessor = collect_preprocessor_info(&processed, &source, &root, &include_roots, file_path);
+
+    let errors = match CodeParser::from_processed(processed, source, &root, options, file_path) {
+        Ok(parser) => return Ok(ParsedFile {
+            classes: parser.parse_classes(file_path),
+            preprocessor,
+            diagnostics: parser.warnings,
+            status: ParseStatus::Complete,
+            errors: Vec::new(),
+        }),
+        Err(errors) => errors,
+    };
+
+    // Only syntax errors are local to a class
+    if !errors.iter().all(|e| matches!(e, ScanError::Parse(_))) {
+        return Err(errors);
+    }
+
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| vec![ScanError::io(file_path, &e)])?;
+    let regions = class_regions(&content, 0..content.len());
+    if regions.is_empty() {
+        return Err(errors);
+    }
+    debug!("Recovering {} top-level classes of {}", regions.len(), file_path.display());
+
+    let overlay = TempDir::new().map_err(|e| vec![ScanError::io(&std::env::temp_dir(), &e)])?;
+    let mut recovery = Recovery {
+        content,
+        root,
+        relative,
+        options,
+        file_path,
+        overlay,
+        classes: Vec::new(),
+        warnings: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    // Statements outside of classes are kept in every attempt, so they have to parse on their own
+    let outside: Vec<Range<usize>> = regions.iter().map(|region| region.range.clone()).collect();
+    match recovery.attempt(&outside) {
+        Ok(parser) => recovery.accept(parser),
+        Err(_) => {
+            debug!("Statements outside of classes in {} don't parse, giving up", file_path.display());
+            return Err(errors);
+        }
+    }
+
+    let group: Vec<&Region> = regions.iter().collect();
+    recovery.split(&group, &[], errors);
+
+    if recovery.classes.is_empty() {
+        return Err(recovery.errors);
+    }
+    debug!("Recovered {} classes of {}, {} errors", recovery.classes.len(), file_path.display(), recovery.errors.len());
+
+    Ok(ParsedFile {
+        classes: recovery.classes,
+        preprocessor,
+        diagnostics: recovery.warnings,
+        status: ParseStatus::Partial,
+        errors: recovery.errors,
+    })
+}
+
+/// A class statement in the original source
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Region {
+    /// From the `class` keyword to the closing `;`
+    range: Range<usize>,
+    /// Class statements inside the body
+    children: Vec<Region>,
+}
+
+struct Recovery<'a> {
+    content: String,
+    root: PathBuf,
+    relative: String,
+    options: &'a ParseOptions,
+    file_path: &'a Path,
+    /// Workspace layer holding the blanked copies of the file
+    overlay: TempDir,
+    classes: Vec<GameClass>,
+    warnings: Vec<Diagnostic>,
+    errors: Vec<ScanError>,
+}
+
+impl Recovery<'_> {
+    /// Parse the file with the `blanked` ranges removed
+    fn attempt(&self, blanked: &[Range<usize>]) -> Result<CodeParser, Vec<ScanError>> {
+        let path = self.overlay.path().join(&self.relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| vec![ScanError::io(parent, &e)])?;
+        }
+        fs::write(&path, blank(&self.content, blanked)).map_err(|e| vec![ScanError::io(&path, &e)])?;
+
+        CodeParser::from_workspace(&self.root, &self.relative, self.options, self.file_path, Some(self.overlay.path()))
+    }
+
+    /// Keep the classes and warnings of a successful attempt
+    fn accept(&mut self, parser: CodeParser) {
+        for warning in parser.warnings() {
+            if !self.warnings.contains(warning) {
+                self.warnings.push(warning.clone());
+            }
+        }
+        for class in parser.parse_classes(self.file_path) {
+            merge_class(&mut self.classes, class);
+        }
+    }
+
+    /// Parse a group of sibling classes, with everything in `blanked` removed
+    fn try_group(&mut self, group: &[&Region], blanked: &[Range<usize>]) {
+        match self.attempt(blanked) {
+            Ok(parser) => self.accept(parser),
+            Err(errors) => self.split(group, blanked, errors),
+        }
+    }
+
+    /// Find the classes of a group that failed to parse with `errors`
+    fn split(&mut self, group: &[&Region], blanked: &[Range<usize>], errors: Vec<ScanError>) {
+        match group {
+            [] => {}
+            [region] if region.children.is_empty() => {
+                debug!("Dropping class at {}..{}", region.range.start, region.range.end);
+                self.errors.extend(errors);
+            }
+            [region] => {
+                // Check the class without its nested classes; if it still fails, its own
+                // properties are broken and the nested classes can't be kept either
+                let without_children: Vec<Range<usize>> = blanked.iter().cloned()
+                    .chain(region.children.iter().map(|child| child.range.clone()))
+                    .collect();
+                match self.attempt(&without_children) {
+                    Ok(parser) => {
+                        self.accept(parser);
+                        let children: Vec<&Region> = region.children.iter().collect();
+                        self.split(&children, blanked, errors);
+                    }
+                    Err(_) => {
+                        debug!("Dropping class at {}..{}", region.range.start, region.range.end);
+                        self.errors.extend(errors);
+                    }
+                }
+            }
+            _ => {
+                let (first, second) = group.split_at(group.len() / 2);
+                let without = |half: &[&Region]| -> Vec<Range<usize>> {
+                    blanked.iter().cloned()
+                        .chain(half.iter().map(|region| region.range.clone()))
+                        .collect()
+                };
+                self.try_group(first, &without(second));
+                self.try_group(second, &without(first));
+            }
+        }
+    }
+}
+
+/// Add a class from one attempt, merging it with the same class from earlier attempts
+///
+/// Containers appear in several attempts, each time with a different subset of
+/// nested classes; the class statement is identified by its position.
+fn merge_class(classes: &mut Vec<GameClass>, class: GameClass) {
+    let existing = classes.iter_mut().find(|existing| {
+        existing.name == class.name
+            && existing.container_class == class.container_class
+            && existing.span.as_ref().map(|span| span.start) == class.span.as_ref().map(|span| span.start)
+    });
+    match existing {
+        Some(existing) => merge_properties(&mut existing.properties, class.properties),
+        None => classes.push(class),
+    }
+}
+
+fn merge_properties(properties: &mut Vec<ClassProperty>, additional: Vec<ClassProperty>) {
+    for property in additional {
+        let existing = properties.iter_mut().find(|existing| existing.name == property.name);
+        match (existing, property.value) {
+            (Some(existing), PropertyValue::Class(class)) => {
+                if let PropertyValue::Class(existing_class) = &mut existing.value {
+                    merge_properties(&mut existing_class.properties, class.properties);
+                }
+            }
+            (Some(_), _) => {}
+            (None, value) => properties.push(ClassProperty { value, ..property }),
+        }
+    }
+}
+
+/// Replace the characters in the byte `ranges` of `content` with a space each, keeping line breaks
+fn blank(content: &str, ranges: &[Range<usize>]) -> String {
+    let mut blanked = vec![false; content.len()];
+    for range in ranges {
+        blanked[range.clone()].fill(true);
+    }
+    content.char_indices()
+        .map(|(i, c)| if blanked[i] && c != '\n' && c != '\r' { ' ' } else { c })
+        .collect()
+}
+
+/// Offset of the character after the one at `i`
+fn next_char(content: &str, i: usize) -> usize {
+    i + content[i..].chars().next().map_or(1, char::len_utf8)
+}
+
+/// Find the class statements with a body in the byte `range` of `content`
+///
+/// The text is walked a character at a time, so offsets are always character
+/// boundaries. Comments, strings and preprocessor directives are skipped. External
+/// declarations (`class Foo;`) are not regions, they stay with their container.
+fn class_regions(content: &str, range: Range<usize>) -> Vec<Region> {
+    let bytes = content.as_bytes();
+    let mut regions = Vec::new();
+    let mut depth = 0usize;
+    let mut i = range.start;
+
+    while i < range.end {
+        if let Some(next) = skip_ignored(content, i) {
+            i = next;
+            continue;
+        }
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => depth = depth.saturating_sub(1),
+            b'c' if depth == 0 && is_keyword(content, i, "class") => {
+                if let Some(region) = class_region(content, i, range.end) {
+                    i = region.range.end;
+                    regions.push(region);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i = next_char(content, i);
+    }
+
+    regions
+}
+
+/// The class statement starting at `start`, if it has a body
+fn class_region(content: &str, start: usize, limit: usize) -> Option<Region> {
+    let bytes = content.as_bytes();
+
+    // Find the opening brace of the body
+    let mut i = start + "class".len();
+    let open = loop {
+        if i >= limit {
+            return None;
+        }
+        if let Some(next) = skip_ignored(content, i) {
+            i = next;
+            continue;
+        }
+        match bytes[i] {
+            b'{' => break i,
+            b';' | b'}' => return None,
+            _ => i = next_char(content, i),
+        }
+    };
+
+    // Find the matching closing brace
+    let mut depth = 0usize;
+    i = open;
+    let close = loop {
+        if i >= limit {
+            return None;
+        }
+        if let Some(next) = skip_ignored(content, i) {
+            i = next;
+            continue;
+        }
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    break i;
+                }
+            }
+            _ => {}
+        }
+        i = next_char(content, i);
+    };
+
+    // Include the terminating `;`
+    let after = close + 1;
+    let end = match content[after..limit].find(|c: char| !c.is_whitespace()) {
+        Some(offset) if bytes[after + offset] == b';' => after + offset + 1,
+        _ => after,
+    };
+
+    Some(Region {
+        range: start..end,
+        children: class_regions(content, open + 1..close),
+    })
+}
+
+/// If a comment, string or preprocessor directive starts at `i`, the offset after it
+fn skip_ignored(content: &str, i: usize) -> Option<usize> {
+    let rest = &content[i..];
+    let end_of_line = |from: usize| content[from..].find('\n').map_or(content.len(), |offset| from + offset);
+
+    if rest.starts_with("//") {
+        Some(end_of_line(i))
+    } else if let Some(comment) = rest.strip_prefix("/*") {
+        Some(comment.find("*/").map_or(content.len(), |offset| i + 2 + offset + 2))
+    } else if let Some(string) = rest.strip_prefix('"') {
+        // Config strings escape quotes by doubling them, which this handles as two strings
+        Some(string.find('"').map_or(content.len(), |offset| i + 1 + offset + 1))
+    } else if rest.starts_with('#') && content[..i].rsplit('\n').next().is_some_and(|line| line.trim().is_empty()) {
+        // Directives continue over lines ending in `\`
+        let mut end = end_of_line(i);
+        while content[..end].trim_end_matches('\r').ends_with('\\') && end < content.len() {
+            end = end_of_line(end + 1);
+        }
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// Check if the identifier `keyword` starts at `i`
+fn is_keyword(content: &str, i: usize, keyword: &str) -> bool {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    content[i..].starts_with(keyword)
+        && !content[..i].ends_with(is_ident)
+        && !content[i + keyword.len()..].starts_with(is_ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_regions() {
+        let content = concat!(
+            "#define MACRO class Fake { \\\n    x = 1; }\n",
+            "class CfgPatches {\n",
+            "    class Test { units[] = {\"class X {}\"}; };\n",
+            "};\n",
+            "// class Commented {};\n",
+            "class External;\n",
+            "class CfgVehicles: Base\n{\n",
+            "    class Car; /* class Hidden {}; */\n",
+            "    class Tank: Car { armor = 1; };\n",
+            "    class Broken { value = ; };\n",
+            "}\n",
+        );
+        let regions = class_regions(content, 0..content.len());
+
+        let text = |region: &Region| &content[region.range.clone()];
+        assert_eq!(regions.len(), 2);
+        assert!(text(&regions[0]).starts_with("class CfgPatches {"));
+        assert!(text(&regions[0]).ends_with("};"));
+        assert_eq!(regions[0].children.len(), 1);
+        assert_eq!(text(&regions[0].children[0]), "class Test { units[] = {\"class X {}\"}; };");
+
+        assert!(text(&regions[1]).starts_with("class CfgVehicles: Base"));
+        assert!(text(&regions[1]).ends_with('}'));
+        let children: Vec<&str> = regions[1].children.iter().map(text).collect();
+        assert_eq!(children, vec!["class Tank: Car { armor = 1; };", "class Broken { value = ; };"]);
+    }
+
+    #[test]
+    fn test_class_regions_with_non_ascii_text() {
+        let content = "class Jacket { displayName = Grüne Jacke; } ;\nclass Шапка { x = 1; };\n";
+        let regions = class_regions(content, 0..content.len());
+
+        let text = |region: &Region| &content[region.range.clone()];
+        let texts: Vec<&str> = regions.iter().map(text).collect();
+        assert_eq!(texts, vec!["class Jacket { displayName = Grüne Jacke; } ;", "class Шапка { x = 1; };"]);
+    }
+
+    #[test]
+    fn test_blank_keeps_offsets() {
+        let content = "class A {\n    x = 1;\n};\nclass B {};\n";
+        let regions = class_regions(content, 0..content.len());
+        let blanked = blank(content, &[regions[0].range.clone()]);
+
+        assert_eq!(blanked.len(), content.len());
+        assert_eq!(blanked.lines().count(), content.lines().count());
+        assert_eq!(blanked.find("class B"), content.find("class B"));
+        assert!(!blanked.contains("class A"));
+
+        // One space per character, so character offsets are kept
+        let content = "class A { x = \"Grüne Jacke\"; };\nclass B {};\n";
+        let regions = class_regions(content, 0..content.len());
+        let blanked = blank(content, &[regions[0].range.clone()]);
+        let char_offset = |text: &str| text[..text.find("class B").unwrap()].chars().count();
+        assert_eq!(blanked.chars().count(), content.chars().count());
+        assert_eq!(char_offset(&blanked), char_offset(content));
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 use std::time::Instant;
-use gamedata_scanner_models::{Scanner, ScanResult, GameClass};
+use gamedata_scanner_models::{Scanner, ScanResult, GameClass, ScanError};
 use crate::parse_file as advanced_parse_file;
 
 /// Advanced scanner implementation
@@ -48,11 +48,8 @@ impl Scanner for AdvancedScanner {
         Ok(result)
     }
     
-    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, String> {
-        match advanced_parse_file(file_path) {
-            Ok(classes) => Ok(classes),
-            Err(errors) => Err(format!("Error parsing file: {:?}", errors))
-        }
+    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
+        advanced_parse_file(file_path)
     }
 }
 
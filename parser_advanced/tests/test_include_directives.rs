@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use parser_advanced::CodeParser;
+    use parser_advanced::{CodeParser, ParseOptions, parse_file, parse_file_with_options};
+    use std::fs;
     use std::path::Path;
+    use tempfile::TempDir;
 
     #[test]
     fn test_basic_includes() {
@@ -122,4 +124,50 @@ mod tests {
         // This test mainly ensures the parser doesn't crash
         assert!(classes.len() > 0);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_relative_include_resolves_against_addon() {
+        let temp_dir = TempDir::new().unwrap();
+        let addon = temp_dir.path().join("addons").join("medical");
+        fs::create_dir_all(&addon).unwrap();
+        fs::write(addon.join("$PBOPREFIX$"), "z\\ace\\addons\\medical").unwrap();
+        fs::write(addon.join("script_component.hpp"), "#define COMPONENT medical\n#define QUOTE(x) #x\n").unwrap();
+        fs::write(addon.join("config.cpp"), r#"
+            #include "script_component.hpp"
+            class CfgPatches {
+                class ace_medical {
+                    name = QUOTE(COMPONENT);
+                };
+            };
+        "#).unwrap();
+        
+        let classes = parse_file(&addon.join("config.cpp")).unwrap();
+        let patch = classes.iter().find(|c| c.name == "ace_medical").unwrap();
+        let name = patch.properties.iter().find(|p| p.name == "name").unwrap();
+        assert_eq!(name.value.as_string(), Some("medical"));
+    }
+
+    #[test]
+    fn test_absolute_include_from_include_root() {
+        let temp_dir = TempDir::new().unwrap();
+        
+        // P-drive style include root holding CBA's macros
+        let include_root = temp_dir.path().join("include");
+        let cba_main = include_root.join("x").join("cba").join("addons").join("main");
+        fs::create_dir_all(&cba_main).unwrap();
+        fs::write(cba_main.join("script_macros_common.hpp"), "#define GVAR(var1) ace_##var1\n").unwrap();
+        
+        let addon = temp_dir.path().join("addons").join("common");
+        fs::create_dir_all(&addon).unwrap();
+        fs::write(addon.join("config.cpp"), r#"
+            #include "\x\cba\addons\main\script_macros_common.hpp"
+            class GVAR(settings) {
+                enabled = 1;
+            };
+        "#).unwrap();
+        
+        let options = ParseOptions::new().with_include_root(&include_root);
+        let classes = parse_file_with_options(&addon.join("config.cpp"), &options).unwrap();
+        assert!(classes.iter().any(|c| c.name == "ace_settings"));
+    }
+}
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use parser_advanced::CodeParser;
+    use gamedata_scanner_models::ClassKind;
     use std::path::Path;
 
     #[test]
@@ -182,4 +183,64 @@ mod tests {
         
         assert!(classes.len() > 0);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_external_and_deleted_classes() {
+        let content = r#"
+            class CfgWeapons {
+                class Rifle_Base_F;
+                class arifle_MX_F: Rifle_Base_F {
+                    displayName = "MX";
+                };
+                delete arifle_Old_F;
+            };
+            delete CfgOldStuff;
+        "#;
+
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_external_and_deleted_classes.hpp");
+        let classes = parser.parse_classes(file_path);
+
+        let cfg_weapons = classes.iter().find(|c| c.name == "CfgWeapons").unwrap();
+        let nested_kind = |name: &str| {
+            cfg_weapons.properties.iter()
+                .find(|p| p.name == name)
+                .and_then(|p| p.value.as_class())
+                .map(|c| c.kind)
+        };
+        assert_eq!(nested_kind("Rifle_Base_F"), Some(ClassKind::External));
+        assert_eq!(nested_kind("arifle_MX_F"), Some(ClassKind::Definition));
+        assert_eq!(nested_kind("arifle_Old_F"), Some(ClassKind::Deleted));
+
+        let deleted = classes.iter().find(|c| c.name == "CfgOldStuff").unwrap();
+        assert_eq!(deleted.kind, ClassKind::Deleted);
+        assert!(cfg_weapons.is_definition());
+    }
+
+    #[test]
+    fn test_definition_after_forward_declaration() {
+        let content = r#"
+            class Base;
+            class Derived;
+            class Base {
+                scope = 2;
+            };
+            class Derived: Base {
+                scope = 1;
+            };
+        "#;
+
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_definition_after_forward_declaration.hpp");
+        let classes = parser.parse_classes(file_path);
+
+        for name in ["Base", "Derived"] {
+            let matching: Vec<_> = classes.iter().filter(|c| c.name == name).collect();
+            assert_eq!(matching.len(), 1, "{} should be listed once", name);
+            assert_eq!(matching[0].kind, ClassKind::Definition);
+            assert_eq!(matching[0].properties.len(), 1);
+        }
+        let derived = classes.iter().find(|c| c.name == "Derived").unwrap();
+        assert_eq!(derived.parent.as_deref(), Some("Base"));
+    }
+}
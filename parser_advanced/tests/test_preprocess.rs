@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use parser_advanced::{preprocess_file, ParseOptions};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_preprocess_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let addon = temp_dir.path().join("addons").join("common");
+        fs::create_dir_all(&addon).unwrap();
+        fs::write(addon.join("script_component.hpp"), "#define GVAR(var1) ace_common_##var1\n#define MASS 10\n").unwrap();
+        fs::write(addon.join("config.cpp"), concat!(
+            "#include \"script_component.hpp\"\n",
+            "class GVAR(settings) {\n",
+            "    mass = MASS;\n",
+            "};\n",
+        )).unwrap();
+
+        let output = preprocess_file(&addon.join("config.cpp"), &ParseOptions::new()).unwrap();
+
+        let text = output.text();
+        assert!(text.contains("class ace_common_settings"));
+        assert!(text.contains("mass = 10;"));
+        assert!(!text.contains("#define"));
+
+        let class_line = output.lines.iter().find(|l| l.text.contains("ace_common_settings")).unwrap();
+        let origin = class_line.origin.as_ref().unwrap();
+        assert_eq!(origin.file, addon.join("config.cpp"));
+        assert_eq!(origin.line, 2);
+
+        let annotated = output.annotated();
+        assert!(annotated.contains(&format!("// #line 2 \"{}\"", addon.join("config.cpp").display())));
+    }
+
+    #[test]
+    fn test_preprocess_missing_include() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "#include \"missing.hpp\"\nclass Test {};\n").unwrap();
+
+        assert!(preprocess_file(&file, &ParseOptions::new()).is_err());
+    }
+}
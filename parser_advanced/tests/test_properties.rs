@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use parser_advanced::CodeParser;
-    use gamedata_scanner_models::PropertyValue;
+    use gamedata_scanner_models::{PropertyValue, PropertyOperator, ArrayElement};
     use std::path::Path;
 
     #[test]
@@ -188,4 +188,145 @@ mod tests {
         assert!(settings.properties.iter()
             .all(|p| matches!(&p.value, PropertyValue::Number(_))));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_float_properties() {
+        let content = r#"
+            class ItemInfo {
+                mass = 0.35;
+                armor = 12;
+                passThrough = 0.5;
+            };
+        "#;
+        
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_float_properties.hpp");
+        let classes = parser.parse_classes(file_path);
+        
+        let item_info = &classes[0];
+        let mass = item_info.properties.iter().find(|p| p.name == "mass").unwrap();
+        assert_eq!(mass.value.as_float(), Some(0.35));
+        
+        let armor = item_info.properties.iter().find(|p| p.name == "armor").unwrap();
+        assert_eq!(armor.value.as_number(), Some(12));
+        assert_eq!(armor.value.as_f64(), Some(12.0));
+        
+        let pass_through = item_info.properties.iter().find(|p| p.name == "passThrough").unwrap();
+        assert!(matches!(pass_through.value, PropertyValue::Float(f) if f == 0.5));
+    }
+
+    #[test]
+    fn test_typed_nested_arrays() {
+        let content = r#"
+            class Weapon {
+                magazineWell[] = {{"CBA_556x45_STANAG", 1}, {"CBA_556x45_G36", 2.5}};
+                mixed[] = {"1", 1};
+            };
+        "#;
+        
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_typed_nested_arrays.hpp");
+        let classes = parser.parse_classes(file_path);
+        
+        let weapon = &classes[0];
+        let wells = weapon.properties.iter().find(|p| p.name == "magazineWell").unwrap();
+        let wells = wells.value.as_array().unwrap();
+        assert_eq!(wells.len(), 2);
+        
+        let first = wells[0].as_array().unwrap();
+        assert_eq!(first[0].as_string(), Some("CBA_556x45_STANAG"));
+        assert_eq!(first[1].as_number(), Some(1));
+        
+        let second = wells[1].as_array().unwrap();
+        assert_eq!(second[1].as_float(), Some(2.5));
+        
+        // Quoted and unquoted numbers stay distinguishable
+        let mixed = weapon.properties.iter().find(|p| p.name == "mixed").unwrap();
+        if let PropertyValue::Array(items) = &mixed.value {
+            assert!(matches!(&items[0], ArrayElement::String(s) if s == "1"));
+            assert!(matches!(&items[1], ArrayElement::Number(1)));
+        } else {
+            panic!("Expected mixed to be an array");
+        }
+    }
+
+    #[test]
+    fn test_array_append_operator() {
+        let content = r#"
+            class CfgWeapons {
+                class Rifle_Base_F {
+                    magazines[] = {"30Rnd_65x39_caseless_mag"};
+                };
+                class arifle_MX_F: Rifle_Base_F {
+                    magazines[] += {"30Rnd_65x39_caseless_mag_Tracer"};
+                    displayName = "MX";
+                };
+            };
+        "#;
+
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_array_append_operator.hpp");
+        let classes = parser.parse_classes(file_path);
+
+        let operator = |class_name: &str, property: &str| {
+            classes.iter()
+                .find(|c| c.name == class_name)
+                .and_then(|c| c.properties.iter().find(|p| p.name == property))
+                .map(|p| p.operator)
+        };
+        assert_eq!(operator("Rifle_Base_F", "magazines"), Some(PropertyOperator::Assign));
+        assert_eq!(operator("arifle_MX_F", "magazines"), Some(PropertyOperator::Append));
+        assert_eq!(operator("arifle_MX_F", "displayName"), Some(PropertyOperator::Assign));
+    }
+
+    #[test]
+    fn test_expression_properties() {
+        let content = r#"
+            class Balance {
+                baseMass = 10;
+                simple = __EVAL(2 * 3 + 1);
+                scaled = __EVAL(baseMass * 1.5);
+                scripted = __EVAL(getNumber (configFile >> "CfgX" >> "y"));
+            };
+        "#;
+
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_expression_properties.hpp");
+        let classes = parser.parse_classes(file_path);
+
+        let balance = classes.iter().find(|c| c.name == "Balance").unwrap();
+        let value = |name: &str| &balance.properties.iter().find(|p| p.name == name).unwrap().value;
+
+        assert_eq!(value("simple").as_f64(), Some(7.0));
+        assert_eq!(value("scaled").as_f64(), Some(15.0));
+        assert!(value("scaled").as_expression().unwrap().contains("baseMass"));
+
+        // Expressions we can't evaluate keep their source text
+        match value("scripted") {
+            PropertyValue::Expression { source, evaluated } => {
+                assert!(source.contains("getNumber"));
+                assert_eq!(*evaluated, None);
+            }
+            other => panic!("Expected an expression, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_expression_after_non_ascii_text() {
+        let content = r#"
+            class Jacket {
+                displayName = "Grüne Jacke — Зелёная куртка";
+                x = __EVAL(2 * 3 + 1);
+            };
+        "#;
+
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_expression_after_non_ascii_text.hpp");
+        let classes = parser.parse_classes(file_path);
+
+        let jacket = classes.iter().find(|c| c.name == "Jacket").unwrap();
+        let x = &jacket.properties.iter().find(|p| p.name == "x").unwrap().value;
+        assert!(x.as_expression().unwrap().contains("2 * 3 + 1"));
+        assert_eq!(x.as_f64(), Some(7.0));
+    }
+}
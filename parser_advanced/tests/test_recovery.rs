@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use gamedata_scanner_models::{ParseStatus, ScanError};
+    use parser_advanced::{parse_file_recovering, ParseOptions};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recovers_classes_around_syntax_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let addon = temp_dir.path().join("addon");
+        fs::create_dir_all(&addon).unwrap();
+        fs::write(addon.join("script_component.hpp"), "#define MASS 10\n").unwrap();
+        fs::write(addon.join("config.cpp"), concat!(
+            "#include \"script_component.hpp\"\n",
+            "class CfgPatches {\n",
+            "    class test_addon { units[] = {}; };\n",
+            "};\n",
+            "class CfgWeapons {\n",
+            "    class Rifle { mass = MASS; };\n",
+            "    class Broken { value = ; };\n",
+            "    class Pistol { mass = 5; };\n",
+            "};\n",
+        )).unwrap();
+        
+        let parsed = parse_file_recovering(&addon.join("config.cpp"), &ParseOptions::new()).unwrap();
+        assert_eq!(parsed.status, ParseStatus::Partial);
+        assert!(!parsed.errors.is_empty());
+        assert!(parsed.errors.iter().all(|e| matches!(e, ScanError::Parse(_))));
+        
+        let find = |name: &str| parsed.classes.iter().find(|c| c.name == name);
+        assert!(find("CfgPatches").is_some());
+        assert!(find("CfgWeapons").is_some());
+        assert!(find("Broken").is_none());
+        
+        let rifle = find("Rifle").expect("Rifle should be recovered");
+        assert_eq!(rifle.container_class.as_deref(), Some("CfgWeapons"));
+        assert_eq!(rifle.properties[0].value.as_number(), Some(10));
+        // Lines refer to the original file
+        assert_eq!(rifle.span.as_ref().unwrap().line, 6);
+        assert_eq!(find("Pistol").unwrap().span.as_ref().unwrap().line, 8);
+        
+        // The container keeps the nested classes of every attempt
+        let weapons = find("CfgWeapons").unwrap();
+        let nested: Vec<&str> = weapons.properties.iter().map(|p| p.name.as_str()).collect();
+        assert!(nested.contains(&"Rifle"));
+        assert!(nested.contains(&"Pistol"));
+        
+        // The preprocessor record is that of the whole file
+        assert_eq!(parsed.preprocessor.definition("MASS").unwrap().line, 1);
+        assert_eq!(parsed.preprocessor.expansions_of("MASS").map(|e| e.line).collect::<Vec<_>>(), vec![6]);
+        assert_eq!(parsed.preprocessor.includes.len(), 1);
+    }
+
+    #[test]
+    fn test_recovers_classes_around_non_ascii_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, concat!(
+            "class CfgWeapons {\n",
+            "    class Rifle { displayName = \"Grüne Jacke — Зелёная\"; mass = 1; };\n",
+            "    class Broken { displayName = Grüne Jacke; value = ; };\n",
+            "    class Pistol { displayName = \"Пистолет\"; mass = 5; };\n",
+            "};\n",
+        )).unwrap();
+        
+        let parsed = parse_file_recovering(&file, &ParseOptions::new()).unwrap();
+        assert_eq!(parsed.status, ParseStatus::Partial);
+        
+        let find = |name: &str| parsed.classes.iter().find(|c| c.name == name);
+        assert!(find("Broken").is_none());
+        assert_eq!(find("Rifle").unwrap().properties[0].value.as_string(), Some("Grüne Jacke — Зелёная"));
+        let pistol = find("Pistol").expect("Pistol should be recovered");
+        assert_eq!(pistol.properties[0].value.as_string(), Some("Пистолет"));
+        assert_eq!(pistol.span.as_ref().unwrap().line, 4);
+        
+        // Attempts with the non-ASCII text blanked still merge into the same classes
+        assert_eq!(parsed.classes.iter().filter(|c| c.name == "Pistol").count(), 1);
+        assert_eq!(parsed.classes.iter().filter(|c| c.name == "CfgWeapons").count(), 1);
+        let weapons = find("CfgWeapons").unwrap();
+        let nested: Vec<&str> = weapons.properties.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(nested.iter().filter(|name| **name == "Pistol").count(), 1);
+        assert!(nested.contains(&"Rifle"));
+    }
+
+    #[test]
+    fn test_complete_file_is_not_partial() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class Test { value = 1; };\n").unwrap();
+        
+        let parsed = parse_file_recovering(&file, &ParseOptions::new()).unwrap();
+        assert_eq!(parsed.status, ParseStatus::Complete);
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.classes.len(), 1);
+    }
+
+    #[test]
+    fn test_unrecoverable_file_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class Test { value = ; };\n").unwrap();
+        
+        let errors = parse_file_recovering(&file, &ParseOptions::new()).unwrap_err();
+        assert!(!errors.is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use gamedata_scanner_models::{ScanError, Severity};
+    use parser_advanced::{parse_file, parse_file_detailed, CodeParser, ParseOptions};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_is_io_error() {
+        let missing = Path::new("does/not/exist/config.cpp");
+        let errors = parse_file(missing).unwrap_err();
+        
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], ScanError::Io(_)));
+        assert_eq!(errors[0].file(), missing);
+        assert!(!errors[0].message().is_empty());
+    }
+
+    #[test]
+    fn test_preprocess_error_carries_code() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "#include \"missing.hpp\"\nclass Test {};\n").unwrap();
+        
+        let errors = parse_file(&file).unwrap_err();
+        assert!(matches!(errors[0], ScanError::Preprocess(_)));
+        assert_eq!(errors[0].severity(), Severity::Error);
+        assert!(errors[0].diagnostic().code.is_some());
+        assert_eq!(errors[0].category(), "Preprocessor Error");
+    }
+
+    #[test]
+    fn test_syntax_error_is_parse_error() {
+        let errors = match CodeParser::new("class Test {\n    value = ;\n};\n") {
+            Ok(_) => panic!("invalid config should not parse"),
+            Err(errors) => errors,
+        };
+        
+        assert!(!errors.is_empty());
+        for error in &errors {
+            assert!(matches!(error, ScanError::Parse(_)));
+            assert!(error.diagnostic().code.is_some());
+        }
+    }
+
+    #[test]
+    fn test_errors_point_at_line_and_column() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class Test {\n    speed = 1;\n    value = ;\n};\n").unwrap();
+        
+        let errors = parse_file_detailed(&file, &ParseOptions::new()).unwrap_err();
+        let span = errors[0].diagnostic().span.as_ref().expect("parse errors should have a span");
+        assert_eq!(span.file, file);
+        assert_eq!(span.line, 3);
+        assert!(span.column > 1);
+        assert!(span.start >= "class Test {\n    speed = 1;\n".len());
+        
+        // Errors in an include point into the include
+        fs::write(temp_dir.path().join("script_component.hpp"), "\n#include \"missing.hpp\"\n").unwrap();
+        fs::write(&file, "#include \"script_component.hpp\"\nclass Test {};\n").unwrap();
+        
+        let errors = parse_file_detailed(&file, &ParseOptions::new()).unwrap_err();
+        let span = errors[0].diagnostic().span.as_ref().expect("preprocess errors should have a span");
+        assert_eq!(errors[0].file(), temp_dir.path().join("script_component.hpp"));
+        assert_eq!(span.line, 2);
+        assert!(span.column >= "#include ".len());
+    }
+
+    #[test]
+    fn test_error_position_after_non_ascii_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.cpp");
+        let position = |display_name: &str| {
+            fs::write(&file, format!("class Test {{\n    displayName = \"{}\";\n    value = ;\n}};\n", display_name)).unwrap();
+            let errors = parse_file_detailed(&file, &ParseOptions::new()).unwrap_err();
+            let span = errors[0].diagnostic().span.clone().expect("parse errors should have a span");
+            (span.line, span.column)
+        };
+        
+        // Same number of characters, many more bytes
+        let ascii = position("Green Jacke Test Test Test");
+        assert_eq!(ascii.0, 3);
+        assert_eq!(position("Grüne Jacke Тест Тест Тест"), ascii);
+    }
+}
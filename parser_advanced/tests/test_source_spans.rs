@@ -0,0 +1,30 @@
+#[cfg(test)]
+mod tests {
+    use parser_advanced::CodeParser;
+    use std::path::Path;
+
+    #[test]
+    fn test_class_and_property_spans() {
+        let content = "class CfgWeapons {\n    class Rifle_Base_F;\n    class arifle_MX_F: Rifle_Base_F {\n        displayName = \"MX 6.5 mm\";\n        class ItemInfo {\n            mass = 4;\n        };\n    };\n};\n";
+
+        let parser = CodeParser::new(content).unwrap();
+        let file_path = Path::new("tests/fixtures/test_source_spans.hpp");
+        let classes = parser.parse_classes(file_path);
+
+        let rifle = classes.iter().find(|c| c.name == "arifle_MX_F").unwrap();
+        let span = rifle.span.as_ref().expect("class should have a span");
+        assert_eq!(span.file, file_path);
+        assert_eq!(span.line, 3);
+        assert_eq!(span.column, 11);
+        assert!(content[span.start..].starts_with("arifle_MX_F"));
+
+        let display_name = rifle.properties.iter().find(|p| p.name == "displayName").unwrap();
+        let span = display_name.span.as_ref().expect("property should have a span");
+        assert_eq!(span.line, 4);
+
+        let item_info = classes.iter()
+            .find(|c| c.name == "ItemInfo" && c.container_class.as_deref() == Some("arifle_MX_F"))
+            .unwrap();
+        assert_eq!(item_info.span.as_ref().unwrap().line, 5);
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use gamedata_scanner_models::conversions::GameClassBuilder;
+    use gamedata_scanner_models::{
+        write_class, write_classes, ArrayElement, ClassKind, GameClass, PropertyOperator, PropertyValue, WriterOptions,
+    };
+    use parser_advanced::CodeParser;
+    use std::path::Path;
+
+    /// ```text
+    /// class Mode_SemiAuto;
+    /// delete CfgOld;
+    /// class CfgVehicles {
+    ///     class Vehicle;
+    ///     class Car: Vehicle {
+    ///         weight = 1.5;
+    ///         displayName = "The ""Big"" Car";
+    ///         speeds[] += {10, 0.35, {"a", 1}};
+    ///         class Turret { armor = 2; };
+    ///     };
+    ///     delete Old;
+    /// };
+    /// ```
+    fn sample() -> Vec<GameClass> {
+        let turret = GameClassBuilder::new("Turret")
+            .container_class("Car")
+            .add_property("armor", 2i64)
+            .build();
+        let mut car = GameClassBuilder::new("Car")
+            .parent("Vehicle")
+            .container_class("CfgVehicles")
+            .add_property("weight", 1.5f32)
+            .add_property("displayName", "The \"Big\" Car")
+            .add_property("speeds", vec![
+                ArrayElement::Number(10),
+                ArrayElement::from(0.35f32),
+                ArrayElement::Array(vec![ArrayElement::String("a".to_string()), ArrayElement::Number(1)]),
+            ])
+            .add_property("Turret", PropertyValue::Class(Box::new(turret)))
+            .build();
+        car.properties[2].operator = PropertyOperator::Append;
+
+        let vehicle = GameClassBuilder::new("Vehicle").container_class("CfgVehicles").kind(ClassKind::External).build();
+        let old = GameClassBuilder::new("Old").container_class("CfgVehicles").kind(ClassKind::Deleted).build();
+        vec![
+            GameClassBuilder::new("Mode_SemiAuto").kind(ClassKind::External).build(),
+            GameClassBuilder::new("CfgOld").kind(ClassKind::Deleted).build(),
+            GameClassBuilder::new("CfgVehicles")
+                .add_property("Vehicle", PropertyValue::Class(Box::new(vehicle)))
+                .add_property("Car", PropertyValue::Class(Box::new(car)))
+                .add_property("Old", PropertyValue::Class(Box::new(old)))
+                .build(),
+        ]
+    }
+
+    fn reparse(text: &str) -> Vec<GameClass> {
+        let parser = CodeParser::new(text).unwrap_or_else(|errors| panic!("{:?} in:\n{}", errors, text));
+        parser.parse_classes(Path::new("written.cpp"))
+    }
+
+    fn find<'a>(classes: &'a [GameClass], name: &str, container: Option<&str>) -> &'a GameClass {
+        classes.iter()
+            .find(|c| c.name == name && c.container_class.as_deref() == container)
+            .unwrap_or_else(|| panic!("class {} not found", name))
+    }
+
+    #[test]
+    fn test_written_classes_parse_back() {
+        let text = write_classes(&sample(), &WriterOptions::default()).unwrap();
+        let classes = reparse(&text);
+
+        assert_eq!(find(&classes, "Mode_SemiAuto", None).kind, ClassKind::External);
+        assert_eq!(find(&classes, "CfgOld", None).kind, ClassKind::Deleted);
+
+        let car = find(&classes, "Car", Some("CfgVehicles"));
+        assert_eq!(car.parent.as_deref(), Some("Vehicle"));
+        assert!(matches!(car.properties[0].value, PropertyValue::Float(w) if w == 1.5));
+        assert_eq!(car.properties[1].value.as_string(), Some("The \"Big\" Car"));
+        assert_eq!(car.properties[2].operator, PropertyOperator::Append);
+        assert!(matches!(&car.properties[2].value, PropertyValue::Array(speeds) if speeds[..] == [
+            ArrayElement::Number(10),
+            ArrayElement::Float(0.35),
+            ArrayElement::Array(vec![ArrayElement::String("a".to_string()), ArrayElement::Number(1)]),
+        ]));
+
+        let turret = find(&classes, "Turret", Some("Car"));
+        assert!(matches!(turret.properties[0].value, PropertyValue::Number(2)));
+
+        // Declarations stay properties of their container
+        let vehicles = find(&classes, "CfgVehicles", None);
+        let kinds: Vec<_> = vehicles.properties.iter()
+            .filter_map(|p| p.value.as_class().map(|class| (class.name.as_str(), class.kind)))
+            .collect();
+        assert!(kinds.contains(&("Vehicle", ClassKind::External)));
+        assert!(kinds.contains(&("Old", ClassKind::Deleted)));
+    }
+
+    #[test]
+    fn test_write_order() {
+        let classes = sample();
+
+        // Declaration order by default
+        let text = write_classes(&classes, &WriterOptions::default()).unwrap();
+        let car = text.find("class Car: Vehicle {").unwrap();
+        assert!(text.find("class Mode_SemiAuto;").unwrap() < text.find("delete CfgOld;").unwrap());
+        assert!(text[car..].find("weight = 1.5;").unwrap() < text[car..].find("displayName = \"The \"\"Big\"\" Car\";").unwrap());
+        assert!(text[car..].contains("speeds[] += {10, 0.35, {\"a\", 1}};"));
+
+        // Sorted by name, values before classes, and stable across a round trip
+        let options = WriterOptions { indent: "\t".to_string(), sort_properties: true };
+        let sorted = write_classes(&classes, &options).unwrap();
+        assert!(sorted.find("delete CfgOld;").unwrap() < sorted.find("class CfgVehicles").unwrap());
+        assert!(sorted.find("class CfgVehicles").unwrap() < sorted.find("class Mode_SemiAuto;").unwrap());
+        let car = sorted.find("\tclass Car: Vehicle {").unwrap();
+        let display_name = sorted[car..].find("displayName").unwrap();
+        assert!(display_name < sorted[car..].find("speeds[]").unwrap());
+        assert!(sorted[car..].find("weight").unwrap() < sorted[car..].find("class Turret").unwrap());
+        assert!(sorted.contains("\n\t\tclass Turret {\n\t\t\tarmor = 2;\n\t\t};\n"));
+
+        assert_eq!(write_classes(&reparse(&sorted), &options).unwrap(), sorted);
+    }
+
+    #[test]
+    fn test_rejects_non_finite_floats() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let class = GameClassBuilder::new("CfgVehicles")
+                .add_property("Car", PropertyValue::Class(Box::new(GameClassBuilder::new("Car")
+                    .container_class("CfgVehicles")
+                    .add_property("speeds", vec![ArrayElement::Float(value)])
+                    .build())))
+                .build();
+            let error = write_class(&class, &WriterOptions::default()).unwrap_err();
+            assert_eq!(error.path, "CfgVehicles/Car/speeds");
+        }
+
+        let class = GameClassBuilder::new("Car").add_property("armor", PropertyValue::Float(f64::NAN)).build();
+        assert!(write_classes(&[class], &WriterOptions::default()).is_err());
+    }
+}
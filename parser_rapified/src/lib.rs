@@ -0,0 +1,493 @@
+//! Reader and writer for rapified (binarized) configs
+//!
+//! Shipped addons usually contain a `config.bin` instead of the `config.cpp`
+//! it was built from. This crate decodes those files into the same
+//! [`GameClass`] model the text parsers produce, and encodes classes back
+//! into that format. Source spans are not available, the binary format
+//! doesn't keep them.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use log::debug;
+use gamedata_scanner_models::{Diagnostic, FileParser, GameClass, ParsedFile, ScanError, ScanResult};
+
+mod reader;
+mod writer;
+pub use writer::{rapify, RapifyError};
+
+// Re-export the scanner module
+pub mod scanner;
+
+/// First four bytes of every rapified config
+pub const MAGIC: &[u8; 4] = b"\0raP";
+
+/// Check if `data` starts with the rapified header
+pub fn is_rapified(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Check if the file at `path` starts with the rapified header
+///
+/// Other `.bin` files (e.g. `texHeaders.bin`) and unreadable files return `false`.
+pub fn is_rapified_file(path: &Path) -> bool {
+    let mut header = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| is_rapified(&header))
+}
+
+/// Decode a rapified config file
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file to decode
+///
+/// # Returns
+///
+/// * `Result<Vec<GameClass>, Vec<ScanError>>` - The classes or the reason the file couldn't be read
+pub fn parse_file(file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
+    parse_file_detailed(file_path).map(|parsed| parsed.classes)
+}
+
+/// Decode a rapified config file, with warnings about the entries that were skipped
+///
+/// Rapified files have no preprocessor record, it is left empty.
+pub fn parse_file_detailed(file_path: &Path) -> Result<ParsedFile, Vec<ScanError>> {
+    debug!("Decoding rapified file: {}", file_path.display());
+    let data = fs::read(file_path).map_err(|e| vec![ScanError::io(file_path, &e)])?;
+    parse_bytes_detailed(&data, file_path)
+}
+
+/// Decode a rapified config already in memory, attributing the classes to `file_path`
+pub fn parse_bytes(data: &[u8], file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
+    parse_bytes_detailed(data, file_path).map(|parsed| parsed.classes)
+}
+
+/// Like [`parse_bytes`], with warnings about the entries that were skipped
+pub fn parse_bytes_detailed(data: &[u8], file_path: &Path) -> Result<ParsedFile, Vec<ScanError>> {
+    let (classes, diagnostics) = reader::decode(data, file_path).map_err(|message| {
+        vec![ScanError::Parse(Diagnostic::new(file_path, format!("Invalid rapified config: {}", message)))]
+    })?;
+    Ok(ParsedFile { classes, diagnostics, ..ParsedFile::default() })
+}
+
+/// Encode classes as a rapified config and write it to `file_path`, see [`rapify`]
+pub fn write_file(file_path: &Path, classes: &[GameClass]) -> io::Result<()> {
+    let data = rapify(classes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(file_path, data)
+}
+
+/// RapifiedFileParser implements the FileParser trait for rapified configs
+pub struct RapifiedFileParser;
+
+impl RapifiedFileParser {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RapifiedFileParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileParser for RapifiedFileParser {
+    fn parse_file(&self, file_path: &Path) -> Vec<GameClass> {
+        parse_file(file_path).unwrap_or_else(|errors| {
+            debug!("Failed to decode file: {:?}", errors);
+            Vec::new()
+        })
+    }
+
+    fn parse_directory(&self, dir_path: &Path) -> ScanResult {
+        let mut files = Vec::new();
+        collect_rapified_files(dir_path, &mut files);
+
+        let mut result = ScanResult::new();
+        result.files_scanned = files.len();
+        for file_path in &files {
+            match parse_file(file_path) {
+                Ok(classes) => result.add_classes(classes),
+                Err(_) => result.files_with_errors += 1,
+            }
+        }
+        result
+    }
+
+    fn name(&self) -> &str {
+        "RapifiedFileParser"
+    }
+}
+
+/// Recursively collect `.bin` files with a rapified header
+pub(crate) fn collect_rapified_files(dir_path: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir_path) else {
+        debug!("Failed to read directory: {}", dir_path.display());
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rapified_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bin")) && is_rapified_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gamedata_scanner_models::{ArrayElement, ClassKind, ConfigTree, PropertyOperator, PropertyValue, Severity};
+    use gamedata_scanner_models::conversions::GameClassBuilder;
+
+    fn asciiz(out: &mut Vec<u8>, s: &str) {
+        out.extend_from_slice(s.as_bytes());
+        out.push(0);
+    }
+
+    fn offset_at(out: &mut [u8], at: usize, value: usize) {
+        out[at..at + 4].copy_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    fn header() -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&8u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out
+    }
+
+    /// Hand-assembled rapified file for:
+    ///
+    /// ```text
+    /// version = 3;
+    /// class CfgPatches {
+    ///     class my_addon { units[] = {"Car"}; weight = 1.5; };
+    /// };
+    /// class CfgVehicles {
+    ///     class Vehicle;
+    ///     class Car: Vehicle { displayName = "Car"; speeds[] += {10, {20}}; };
+    ///     delete Old;
+    /// };
+    /// ```
+    fn sample() -> Vec<u8> {
+        let mut out = header();
+
+        // Root body
+        asciiz(&mut out, "");
+        out.push(3);
+        out.extend_from_slice(&[1, 2]);
+        asciiz(&mut out, "version");
+        out.extend_from_slice(&3i32.to_le_bytes());
+        out.push(0);
+        asciiz(&mut out, "CfgPatches");
+        let patches_offset = out.len();
+        out.extend_from_slice(&[0; 4]);
+        out.push(0);
+        asciiz(&mut out, "CfgVehicles");
+        let vehicles_offset = out.len();
+        out.extend_from_slice(&[0; 4]);
+
+        // CfgPatches
+        let at = out.len();
+        offset_at(&mut out, patches_offset, at);
+        asciiz(&mut out, "");
+        out.push(1);
+        out.push(0);
+        asciiz(&mut out, "my_addon");
+        let my_addon_offset = out.len();
+        out.extend_from_slice(&[0; 4]);
+
+        // my_addon
+        let at = out.len();
+        offset_at(&mut out, my_addon_offset, at);
+        asciiz(&mut out, "");
+        out.push(2);
+        out.push(2);
+        asciiz(&mut out, "units");
+        out.extend_from_slice(&[1, 0]);
+        asciiz(&mut out, "Car");
+        out.extend_from_slice(&[1, 1]);
+        asciiz(&mut out, "weight");
+        out.extend_from_slice(&1.5f32.to_le_bytes());
+
+        // CfgVehicles
+        let at = out.len();
+        offset_at(&mut out, vehicles_offset, at);
+        asciiz(&mut out, "");
+        out.push(3);
+        out.push(3);
+        asciiz(&mut out, "Vehicle");
+        out.push(0);
+        asciiz(&mut out, "Car");
+        let car_offset = out.len();
+        out.extend_from_slice(&[0; 4]);
+        out.push(4);
+        asciiz(&mut out, "Old");
+
+        // Car
+        let at = out.len();
+        offset_at(&mut out, car_offset, at);
+        asciiz(&mut out, "Vehicle");
+        out.push(2);
+        out.extend_from_slice(&[1, 0]);
+        asciiz(&mut out, "displayName");
+        asciiz(&mut out, "Car");
+        out.push(5);
+        out.extend_from_slice(&1u32.to_le_bytes());
+        asciiz(&mut out, "speeds");
+        out.extend_from_slice(&[2, 2]);
+        out.extend_from_slice(&10i32.to_le_bytes());
+        out.extend_from_slice(&[3, 1, 2]);
+        out.extend_from_slice(&20i32.to_le_bytes());
+
+        out
+    }
+
+    #[test]
+    fn test_decode_classes() {
+        let classes = parse_bytes(&sample(), Path::new("config.bin")).unwrap();
+        let names: Vec<_> = classes.iter().map(|c| (c.name.as_str(), c.container_class.as_deref())).collect();
+        assert_eq!(names, vec![
+            ("my_addon", Some("CfgPatches")),
+            ("CfgPatches", None),
+            ("Car", Some("CfgVehicles")),
+            ("CfgVehicles", None),
+        ]);
+
+        let my_addon = &classes[0];
+        assert!(matches!(&my_addon.properties[0].value, PropertyValue::Array(units) if units[..] == [ArrayElement::String("Car".to_string())]));
+        assert!(matches!(my_addon.properties[1].value, PropertyValue::Float(w) if w == 1.5));
+
+        let car = &classes[2];
+        assert_eq!(car.parent.as_deref(), Some("Vehicle"));
+        assert!(car.span.is_none());
+        assert_eq!(car.properties[0].value.as_string(), Some("Car"));
+        assert_eq!(car.properties[1].operator, PropertyOperator::Append);
+        assert!(matches!(&car.properties[1].value, PropertyValue::Array(speeds) if speeds[..] == [
+            ArrayElement::Number(10),
+            ArrayElement::Array(vec![ArrayElement::Number(20)]),
+        ]));
+
+        // Declarations stay properties of their container
+        let vehicles = &classes[3];
+        let kinds: Vec<_> = vehicles.properties.iter().map(|p| match &p.value {
+            PropertyValue::Class(class) => (class.name.as_str(), class.kind),
+            _ => panic!("unexpected property {}", p.name),
+        }).collect();
+        assert_eq!(kinds, vec![
+            ("Vehicle", ClassKind::External),
+            ("Car", ClassKind::Definition),
+            ("Old", ClassKind::Deleted),
+        ]);
+    }
+
+    #[test]
+    fn test_rejects_broken_files() {
+        let path = Path::new("config.bin");
+        assert!(!is_rapified(b"class Foo {};"));
+
+        let errors = parse_bytes(b"class Foo {};", path).unwrap_err();
+        assert!(matches!(&errors[..], [ScanError::Parse(_)]));
+
+        // Truncated in the middle of the Car body
+        let sample = sample();
+        let errors = parse_bytes(&sample[..sample.len() - 6], path).unwrap_err();
+        assert!(errors[0].message().contains("Unexpected end of file"));
+    }
+
+    #[test]
+    fn test_floats_keep_their_decimal_value() {
+        let path = Path::new("config.bin");
+        let class = GameClassBuilder::new("CfgVehicles")
+            .file_path(path)
+            .add_property("mass", 0.35)
+            .add_property("speeds", vec![ArrayElement::Float(0.35), ArrayElement::Float(1.1)])
+            .build();
+
+        let decoded = parse_bytes(&rapify(&[class]).unwrap(), path).unwrap();
+        assert!(matches!(decoded[0].properties[0].value, PropertyValue::Float(mass) if mass == 0.35));
+        assert!(matches!(&decoded[0].properties[1].value, PropertyValue::Array(speeds) if speeds[..] == [
+            ArrayElement::Float(0.35),
+            ArrayElement::Float(1.1),
+        ]));
+    }
+
+    #[test]
+    fn test_rejects_shared_class_bodies() {
+        // Two classes pointing at the same body, which nests the body twice
+        // more per level in crafted files
+        let mut out = header();
+        asciiz(&mut out, "");
+        out.push(2);
+        let mut offsets = Vec::new();
+        for name in ["A", "B"] {
+            out.push(0);
+            asciiz(&mut out, name);
+            offsets.push(out.len());
+            out.extend_from_slice(&[0; 4]);
+        }
+        let body = out.len();
+        for at in offsets {
+            offset_at(&mut out, at, body);
+        }
+        asciiz(&mut out, "");
+        out.push(0);
+
+        let errors = parse_bytes(&out, Path::new("config.bin")).unwrap_err();
+        assert!(errors[0].message().contains("used by more than one class"));
+    }
+
+    #[test]
+    fn test_skips_unknown_array_flags() {
+        // class A { x[] ?= {1}; y = 2; }; with an unknown flag for x
+        let mut out = header();
+        asciiz(&mut out, "");
+        out.push(1);
+        out.push(0);
+        asciiz(&mut out, "A");
+        let at = out.len();
+        out.extend_from_slice(&[0; 4]);
+        let body = out.len();
+        offset_at(&mut out, at, body);
+        asciiz(&mut out, "");
+        out.push(2);
+        out.push(5);
+        out.extend_from_slice(&2u32.to_le_bytes());
+        asciiz(&mut out, "x");
+        out.extend_from_slice(&[1, 2]);
+        out.extend_from_slice(&1i32.to_le_bytes());
+        out.extend_from_slice(&[1, 2]);
+        asciiz(&mut out, "y");
+        out.extend_from_slice(&2i32.to_le_bytes());
+
+        let parsed = parse_bytes_detailed(&out, Path::new("config.bin")).unwrap();
+        let names: Vec<_> = parsed.classes[0].properties.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["y"]);
+        assert!(matches!(&parsed.diagnostics[..], [warning] if warning.severity == Severity::Warning
+            && warning.message.contains("unsupported array flags 2")));
+    }
+
+    #[test]
+    fn test_header_check_on_disk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = temp_dir.path().join("config.bin");
+        fs::write(&config, sample()).unwrap();
+        let tex_headers = temp_dir.path().join("texHeaders.bin");
+        fs::write(&tex_headers, b"0DHT\x01\x00\x00\x00").unwrap();
+
+        assert!(is_rapified_file(&config));
+        assert!(!is_rapified_file(&tex_headers));
+        assert!(!is_rapified_file(&temp_dir.path().join("missing.bin")));
+
+        let result = RapifiedFileParser::new().parse_directory(temp_dir.path());
+        assert_eq!(result.files_scanned, 1);
+        assert_eq!(result.classes_found, 4);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = Path::new("config.bin");
+        let classes = parse_bytes(&sample(), path).unwrap();
+
+        let data = rapify(&classes).unwrap();
+        let decoded = parse_bytes(&data, path).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", classes));
+
+        // Encoding is deterministic
+        assert_eq!(rapify(&decoded).unwrap(), data);
+    }
+
+    #[test]
+    fn test_round_trip_top_level_declarations() {
+        let path = Path::new("config.bin");
+        let classes = vec![
+            GameClassBuilder::new("Mode_SemiAuto").file_path(path).kind(ClassKind::External).build(),
+            GameClassBuilder::new("CfgOld").file_path(path).kind(ClassKind::Deleted).build(),
+            GameClassBuilder::new("CfgWeapons").file_path(path).add_property("version", 1i64).build(),
+        ];
+
+        let decoded = parse_bytes(&rapify(&classes).unwrap(), path).unwrap();
+        let kinds: Vec<_> = decoded.iter()
+            .map(|class| (class.name.as_str(), class.kind, class.container_class.as_deref()))
+            .collect();
+        assert_eq!(kinds, vec![
+            ("Mode_SemiAuto", ClassKind::External, None),
+            ("CfgOld", ClassKind::Deleted, None),
+            ("CfgWeapons", ClassKind::Definition, None),
+        ]);
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", classes));
+    }
+
+    #[test]
+    fn test_round_trip_merged_tree() {
+        let path = Path::new("config.bin");
+        let base = parse_bytes(&sample(), path).unwrap();
+
+        // A patch that updates Car, keeping its base, and adds a class inheriting from it
+        let mut car = GameClassBuilder::new("Car")
+            .parent("Vehicle")
+            .file_path("patch.cpp")
+            .container_class("CfgVehicles")
+            .add_property("displayName", "Patched")
+            .add_property("speeds", vec![ArrayElement::Number(30)])
+            .build();
+        car.properties[1].operator = PropertyOperator::Append;
+        let tank = GameClassBuilder::new("Tank")
+            .parent("Car")
+            .file_path("patch.cpp")
+            .container_class("CfgVehicles")
+            .add_property("armor", i64::from(i32::MAX) + 1)
+            .build();
+        let patch = GameClassBuilder::new("CfgVehicles")
+            .file_path("patch.cpp")
+            .add_property("Car", PropertyValue::Class(Box::new(car)))
+            .add_property("Tank", PropertyValue::Class(Box::new(tank)))
+            .build();
+
+        let tree = ConfigTree::from_classes(base.iter().chain([&patch]));
+        let data = rapify(&tree.to_game_classes()).unwrap();
+        let decoded = ConfigTree::from_classes(&parse_bytes(&data, path).unwrap());
+
+        let car = decoded.get("CfgVehicles/Car").unwrap();
+        assert_eq!(car.parent_name.as_deref(), Some("Vehicle"));
+        assert_eq!(car.property("displayName").unwrap().value.as_string(), Some("Patched"));
+        assert!(matches!(&car.property("speeds").unwrap().value, PropertyValue::Array(speeds) if speeds[..] == [
+            ArrayElement::Number(10),
+            ArrayElement::Array(vec![ArrayElement::Number(20)]),
+            ArrayElement::Number(30),
+        ]));
+
+        let tank = decoded.get("CfgVehicles/Tank").unwrap();
+        assert_eq!(decoded.parent_of(decoded.id_of("CfgVehicles/Tank").unwrap()).unwrap().path, "CfgVehicles/Car");
+        assert!(matches!(tank.property("armor").unwrap().value, PropertyValue::Number(n) if n == i64::from(i32::MAX) + 1));
+        assert_eq!(decoded.get("CfgVehicles/Vehicle").unwrap().kind, ClassKind::External);
+        assert_eq!(decoded.get("CfgPatches/my_addon").unwrap().properties.len(), 2);
+    }
+
+    #[test]
+    fn test_rapify_rejects_unexpanded_values() {
+        let class = GameClassBuilder::new("CfgVehicles")
+            .add_property("Car", PropertyValue::Class(Box::new(GameClassBuilder::new("Car")
+                .container_class("CfgVehicles")
+                .add_property("armor", PropertyValue::Expression { source: "ARMOR * 2".to_string(), evaluated: None })
+                .build())))
+            .build();
+
+        let error = rapify(&[class]).unwrap_err();
+        assert_eq!(error.path, "CfgVehicles/Car/armor");
+        assert!(error.to_string().contains("ARMOR * 2"));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let class = GameClassBuilder::new("CfgPatches")
+            .add_property("units", vec![ArrayElement::Macro { name: "UNITS".to_string(), args: Vec::new() }])
+            .build();
+        let error = write_file(&temp_dir.path().join("config.bin"), &[class]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,264 @@
+//! Decoder for the rapified config format
+//!
+//! Layout (all integers little-endian):
+//!
+//! * Header: `\0raP`, `0u32`, `8u32`, offset of the enum table (`u32`)
+//! * Class body: parent name (asciiz, empty for none), entry count
+//!   (compressed int), entries
+//! * Entry: type byte, then
+//!   * `0` class: name, offset of its body (`u32`)
+//!   * `1` value: subtype byte, name, value
+//!   * `2` array: name, array
+//!   * `3` external class: name
+//!   * `4` deleted class: name
+//!   * `5` array with flags: flags (`u32`, `1` = `+=`), name, array
+//!
+//! Every class body has its own offset, a body reached twice means the file
+//! was crafted to make decoding blow up and is rejected.
+//! * Array: element count (compressed int), elements of type byte + value
+//!
+//! Value types are `0` string, `1` float (`f32`), `2` int (`i32`), `3`
+//! nested array (arrays only), `4` variable name and `6` int64.
+
+use std::collections::HashSet;
+use std::path::Path;
+use gamedata_scanner_models::{ArrayElement, ClassKind, ClassProperty, Diagnostic, GameClass, PropertyOperator, PropertyValue, Severity};
+
+use crate::MAGIC;
+
+/// Deepest class nesting accepted, protects against offset loops in broken files
+const MAX_DEPTH: usize = 64;
+
+/// Decode a rapified config into classes, in the same shape as the text parsers
+///
+/// Every class with a body is returned, nested classes before their container,
+/// and nested classes, external declarations and deletions also appear as
+/// [`PropertyValue::Class`] properties of their container. Top-level external
+/// declarations and deletions have no container and are returned as classes.
+/// Entries that can't be represented are skipped and reported as warnings.
+pub(crate) fn decode(data: &[u8], file_path: &Path) -> Result<(Vec<GameClass>, Vec<Diagnostic>), String> {
+    let mut reader = Reader { data, pos: 0, file_path, visited: HashSet::new(), warnings: Vec::new() };
+
+    if reader.take(4)? != MAGIC {
+        return Err("Missing rapified header".to_string());
+    }
+    let (zero, eight) = (reader.u32()?, reader.u32()?);
+    if zero != 0 || eight != 8 {
+        return Err(format!("Unsupported rapified header ({}, {})", zero, eight));
+    }
+    let _enum_offset = reader.u32()?;
+
+    // Root properties have no class to live in, only classes are kept
+    reader.visited.insert(reader.pos);
+    let root = reader.class_body("", None, 0)?;
+    let mut classes = Vec::new();
+    for property in root.properties {
+        if let PropertyValue::Class(class) = property.value {
+            if class.is_definition() {
+                push_flattened(*class, &mut classes);
+            } else {
+                classes.push(*class);
+            }
+        }
+    }
+
+    Ok((classes, reader.warnings))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    file_path: &'a Path,
+    /// Offsets of the class bodies read so far
+    visited: HashSet<usize>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl<'a> Reader<'a> {
+    /// Read the class body at the current position
+    fn class_body(&mut self, name: &str, container: Option<&str>, depth: usize) -> Result<GameClass, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("Classes nested deeper than {} levels", MAX_DEPTH));
+        }
+
+        let parent = self.asciiz()?;
+        let mut class = GameClass::with_container(
+            name.to_string(),
+            (!parent.is_empty()).then_some(parent),
+            self.file_path.to_path_buf(),
+            container.map(str::to_string),
+        );
+
+        let count = self.compressed_int()?;
+        for _ in 0..count {
+            if let Some(property) = self.entry(&class.name, depth)? {
+                class.properties.push(property);
+            }
+        }
+
+        Ok(class)
+    }
+
+    /// Read one entry of the class called `container`, `None` if it was skipped
+    fn entry(&mut self, container: &str, depth: usize) -> Result<Option<ClassProperty>, String> {
+        // The root body has no name, its classes have no container
+        let container_name = (depth > 0).then_some(container);
+
+        let entry_type = self.u8()?;
+        let (name, value, operator) = match entry_type {
+            0 => {
+                let name = self.asciiz()?;
+                let offset = self.u32()? as usize;
+
+                if !self.visited.insert(offset) {
+                    return Err(format!("Class body at offset {} is used by more than one class", offset));
+                }
+
+                let resume = self.pos;
+                self.seek(offset)?;
+                let class = self.class_body(&name, container_name, depth + 1)?;
+                self.pos = resume;
+
+                (name, PropertyValue::Class(Box::new(class)), PropertyOperator::Assign)
+            }
+            1 => {
+                let subtype = self.u8()?;
+                let name = self.asciiz()?;
+                let value = match subtype {
+                    0 | 4 => PropertyValue::String(self.asciiz()?),
+                    1 => PropertyValue::from(self.f32()?),
+                    2 => PropertyValue::Number(self.i32()? as i64),
+                    6 => PropertyValue::Number(self.i64()?),
+                    other => return Err(format!("Unknown value type {} for `{}`", other, name)),
+                };
+                (name, value, PropertyOperator::Assign)
+            }
+            2 => {
+                let name = self.asciiz()?;
+                (name, PropertyValue::Array(self.array(0)?), PropertyOperator::Assign)
+            }
+            3 | 4 => {
+                let name = self.asciiz()?;
+                let kind = if entry_type == 3 { ClassKind::External } else { ClassKind::Deleted };
+                let mut class = GameClass::with_container(
+                    name.clone(),
+                    None,
+                    self.file_path.to_path_buf(),
+                    container_name.map(str::to_string),
+                );
+                class.kind = kind;
+                (name, PropertyValue::Class(Box::new(class)), PropertyOperator::Assign)
+            }
+            5 => {
+                let flags = self.u32()?;
+                let name = self.asciiz()?;
+                let array = self.array(0)?;
+                if flags != 1 {
+                    self.warnings.push(Diagnostic::new(
+                        self.file_path,
+                        format!("Skipped `{}` in `{}`: unsupported array flags {}", name, container, flags),
+                    ).with_severity(Severity::Warning));
+                    return Ok(None);
+                }
+                (name, PropertyValue::Array(array), PropertyOperator::Append)
+            }
+            other => return Err(format!("Unknown entry type {}", other)),
+        };
+
+        let mut property = ClassProperty::new(name, value);
+        property.operator = operator;
+        Ok(Some(property))
+    }
+
+    /// Read an array at the current position
+    fn array(&mut self, depth: usize) -> Result<Vec<ArrayElement>, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("Arrays nested deeper than {} levels", MAX_DEPTH));
+        }
+
+        let count = self.compressed_int()?;
+        let mut elements = Vec::new();
+        for _ in 0..count {
+            let element = match self.u8()? {
+                0 | 4 => ArrayElement::String(self.asciiz()?),
+                1 => ArrayElement::from(self.f32()?),
+                2 => ArrayElement::Number(self.i32()? as i64),
+                3 => ArrayElement::Array(self.array(depth + 1)?),
+                6 => ArrayElement::Number(self.i64()?),
+                other => return Err(format!("Unknown array element type {}", other)),
+            };
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+
+    fn seek(&mut self, offset: usize) -> Result<(), String> {
+        if offset > self.data.len() {
+            return Err(format!("Class offset {} is past the end of the file", offset));
+        }
+        self.pos = offset;
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.data.len())
+            .ok_or_else(|| format!("Unexpected end of file at offset {}", self.pos))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Read a zero-terminated string
+    fn asciiz(&mut self) -> Result<String, String> {
+        let len = self.data[self.pos.min(self.data.len())..].iter().position(|&b| b == 0)
+            .ok_or_else(|| format!("Unterminated string at offset {}", self.pos))?;
+        let bytes = self.take(len)?;
+        self.pos += 1;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    /// Read a 7-bit variable length integer
+    fn compressed_int(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(format!("Invalid compressed integer before offset {}", self.pos))
+    }
+}
+
+/// Push the nested definitions of `class`, then `class` itself
+fn push_flattened(class: GameClass, classes: &mut Vec<GameClass>) {
+    for property in &class.properties {
+        if let PropertyValue::Class(nested) = &property.value {
+            if nested.is_definition() {
+                push_flattened((**nested).clone(), classes);
+            }
+        }
+    }
+    classes.push(class);
+}
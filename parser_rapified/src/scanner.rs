@@ -0,0 +1,51 @@
+use std::path::Path;
+use std::time::Instant;
+use gamedata_scanner_models::{Scanner, ScanResult, GameClass, ScanError};
+use crate::{collect_rapified_files, parse_file};
+
+/// Rapified config scanner implementation
+pub struct RapifiedScanner;
+
+impl RapifiedScanner {
+    /// Create a new rapified scanner
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RapifiedScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scanner for RapifiedScanner {
+    fn scan_directory(&self, dir_path: &Path) -> Result<ScanResult, String> {
+        let start_time = Instant::now();
+
+        if !dir_path.is_dir() {
+            return Err(format!("Directory does not exist: {}", dir_path.display()));
+        }
+
+        let mut files = Vec::new();
+        collect_rapified_files(dir_path, &mut files);
+
+        let mut result = ScanResult::new();
+        result.files_scanned = files.len();
+
+        for file_path in &files {
+            match self.parse_file(file_path) {
+                Ok(file_classes) => result.add_classes(file_classes),
+                Err(_) => result.files_with_errors += 1,
+            }
+        }
+
+        result.scan_time_ms = Some(start_time.elapsed().as_millis() as u64);
+
+        Ok(result)
+    }
+
+    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
+        parse_file(file_path)
+    }
+}
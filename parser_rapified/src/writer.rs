@@ -0,0 +1,223 @@
+//! Encoder for the rapified config format, the inverse of [`crate::parse_bytes`]
+//!
+//! Class bodies are written right after the body of their container, in
+//! definition order, followed by an empty enum table. See the reader module
+//! for the layout.
+
+use std::fmt;
+use std::path::PathBuf;
+use gamedata_scanner_models::{ArrayElement, ClassKind, ClassProperty, GameClass, PropertyOperator, PropertyValue};
+
+use crate::MAGIC;
+
+/// A value that has no rapified representation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RapifyError {
+    /// Path of the offending class or property, e.g. `CfgVehicles/Car/speed`
+    pub path: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl fmt::Display for RapifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot rapify `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for RapifyError {}
+
+/// Encode classes as a rapified config
+///
+/// Only classes without a container are written at the top level, so the
+/// flattened output of a parser can be passed as is; nested classes,
+/// external declarations and deletions are taken from the
+/// [`PropertyValue::Class`] properties of their container.
+///
+/// Unevaluated expressions and unexpanded macros can't be encoded and
+/// return an error.
+pub fn rapify(classes: &[GameClass]) -> Result<Vec<u8>, RapifyError> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&8u32.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+
+    // The root body lists the top-level classes like a class lists nested ones
+    let mut root = GameClass::new(String::new(), None, PathBuf::new());
+    root.properties = classes.iter()
+        .filter(|class| class.container_class.is_none())
+        .map(|class| ClassProperty::new(class.name.clone(), PropertyValue::Class(Box::new(class.clone()))))
+        .collect();
+
+    let mut writer = Writer { out };
+    writer.class(&root, "")?;
+
+    // Empty enum table
+    let enum_offset = writer.out.len();
+    writer.patch(12, enum_offset);
+    writer.out.extend_from_slice(&0u32.to_le_bytes());
+
+    Ok(writer.out)
+}
+
+struct Writer {
+    out: Vec<u8>,
+}
+
+impl Writer {
+    /// Write the body of a class at the current position, then the bodies of its nested classes
+    fn class(&mut self, class: &GameClass, path: &str) -> Result<(), RapifyError> {
+        self.asciiz(class.parent.as_deref().unwrap_or(""));
+        self.compressed_int(class.properties.len());
+
+        let mut pending = Vec::new();
+        for property in &class.properties {
+            let property_path = join(path, &property.name);
+            match &property.value {
+                PropertyValue::Class(nested) => match nested.kind {
+                    ClassKind::Definition => {
+                        self.out.push(0);
+                        self.asciiz(&nested.name);
+                        pending.push((self.out.len(), nested.as_ref()));
+                        self.out.extend_from_slice(&[0; 4]);
+                    }
+                    ClassKind::External => {
+                        self.out.push(3);
+                        self.asciiz(&nested.name);
+                    }
+                    ClassKind::Deleted => {
+                        self.out.push(4);
+                        self.asciiz(&nested.name);
+                    }
+                },
+                PropertyValue::Array(elements) => {
+                    if property.operator == PropertyOperator::Append {
+                        self.out.push(5);
+                        self.out.extend_from_slice(&1u32.to_le_bytes());
+                    } else {
+                        self.out.push(2);
+                    }
+                    self.asciiz(&property.name);
+                    self.array(elements, &property_path)?;
+                }
+                value => {
+                    self.out.push(1);
+                    let start = self.out.len();
+                    self.out.push(0);
+                    self.asciiz(&property.name);
+                    let subtype = self.scalar(value, &property_path)?;
+                    self.out[start] = subtype;
+                }
+            }
+        }
+
+        for (offset_at, nested) in pending {
+            let offset = self.out.len();
+            self.patch(offset_at, offset);
+            self.class(nested, &join(path, &nested.name))?;
+        }
+        Ok(())
+    }
+
+    /// Write a single value, returning its type byte
+    fn scalar(&mut self, value: &PropertyValue, path: &str) -> Result<u8, RapifyError> {
+        match value {
+            PropertyValue::String(s) => {
+                self.asciiz(s);
+                Ok(0)
+            }
+            PropertyValue::Float(f) => {
+                self.out.extend_from_slice(&(*f as f32).to_le_bytes());
+                Ok(1)
+            }
+            PropertyValue::Number(n) => Ok(self.number(*n)),
+            PropertyValue::Expression { evaluated: Some(value), .. } => {
+                self.out.extend_from_slice(&(*value as f32).to_le_bytes());
+                Ok(1)
+            }
+            PropertyValue::Expression { source, evaluated: None } => Err(RapifyError {
+                path: path.to_string(),
+                message: format!("expression `{}` was not evaluated", source),
+            }),
+            PropertyValue::Array(_) | PropertyValue::Class(_) => unreachable!("handled by the caller"),
+        }
+    }
+
+    fn array(&mut self, elements: &[ArrayElement], path: &str) -> Result<(), RapifyError> {
+        self.compressed_int(elements.len());
+        for element in elements {
+            match element {
+                ArrayElement::String(s) => {
+                    self.out.push(0);
+                    self.asciiz(s);
+                }
+                ArrayElement::Float(f) => {
+                    self.out.push(1);
+                    self.out.extend_from_slice(&(*f as f32).to_le_bytes());
+                }
+                ArrayElement::Number(n) => {
+                    let start = self.out.len();
+                    self.out.push(0);
+                    self.out[start] = self.number(*n);
+                }
+                ArrayElement::Array(nested) => {
+                    self.out.push(3);
+                    self.array(nested, path)?;
+                }
+                ArrayElement::Macro { name, .. } => {
+                    return Err(RapifyError {
+                        path: path.to_string(),
+                        message: format!("macro `{}` was not expanded", name),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write an integer as int32 if it fits, int64 otherwise, returning its type byte
+    fn number(&mut self, n: i64) -> u8 {
+        match i32::try_from(n) {
+            Ok(n) => {
+                self.out.extend_from_slice(&n.to_le_bytes());
+                2
+            }
+            Err(_) => {
+                self.out.extend_from_slice(&n.to_le_bytes());
+                6
+            }
+        }
+    }
+
+    fn asciiz(&mut self, s: &str) {
+        self.out.extend_from_slice(s.as_bytes());
+        self.out.push(0);
+    }
+
+    fn compressed_int(&mut self, value: usize) {
+        let mut value = value as u32;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.out.push(byte);
+                return;
+            }
+            self.out.push(byte | 0x80);
+        }
+    }
+
+    /// Overwrite the `u32` at `at` with `value`
+    fn patch(&mut self, at: usize, value: usize) {
+        self.out[at..at + 4].copy_from_slice(&(value as u32).to_le_bytes());
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
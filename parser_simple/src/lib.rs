@@ -1,12 +1,13 @@
 use std::path::Path;
 use std::fs;
-use regex::Regex;
 use log::{debug, trace};
 use std::collections::HashMap;
-use gamedata_scanner_models::{GameClass, ScanResult, FileParser};
+use gamedata_scanner_models::{GameClass, ClassKind, ClassProperty, Fidelity, PropertyValue, ScanResult, ScanError, FileParser, SourceSpan};
+use tokenizer::{tokenize, Token, TokenKind};
 
 // Re-export the scanner module
 pub mod scanner;
+mod tokenizer;
 
 /// SimpleFileParser implements the FileParser trait for the simple parser
 pub struct SimpleFileParser {
@@ -35,60 +36,114 @@ impl FileParser for SimpleFileParser {
     }
 }
 
-/// A simple parser that scans for "class NAME" and "class NAME : PARENT" patterns
-pub struct SimpleClassScanner {
-    class_regex: Regex,
-}
+/// A simple parser that finds classes without preprocessing
+///
+/// Walks the tokens of a file, tracking brace depth to record container
+/// classes and picking up `name = value;` properties with a plain string or
+/// number value. Anything else (arrays, macros, expressions) is skipped.
+#[derive(Debug, Default)]
+pub struct SimpleClassScanner;
 
 impl SimpleClassScanner {
     pub fn new() -> Self {
-        // Pattern to match:
-        // - Optional whitespace
-        // - "class" keyword
-        // - Whitespace
-        // - Class name (capturing group 1)
-        // - Optional whitespace
-        // - Optional ":" followed by whitespace and parent class name (capturing group 2)
-        // - Optional whitespace
-        // - Either "{" or ";"
-        let pattern = r"(?m)^\s*class\s+(\w+)(?:\s*:\s*(\w+))?\s*[{;]";
-        Self {
-            class_regex: Regex::new(pattern).unwrap(),
-        }
+        Self
     }
 
+    /// Scan a file, returning no classes if it can't be read
     pub fn scan_file(&self, file_path: &Path) -> Vec<GameClass> {
+        self.try_scan_file(file_path).unwrap_or_else(|errors| {
+            debug!("Failed to read file: {:?}", errors);
+            Vec::new()
+        })
+    }
+    
+    /// Scan a file, reporting read failures
+    pub fn try_scan_file(&self, file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
         debug!("Simple scanning file: {}", file_path.display());
         
-        let content = match fs::read_to_string(file_path) {
-            Ok(content) => content,
-            Err(e) => {
-                debug!("Failed to read file: {}", e);
-                return Vec::new();
-            }
-        };
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| vec![ScanError::io(file_path, &e)])?;
 
-        let mut classes = Vec::new();
+        let classes = self.scan_content(&content, file_path);
         
-        for capture in self.class_regex.captures_iter(&content) {
-            let name = capture.get(1).unwrap().as_str().to_string();
-            let parent = capture.get(2).map(|m| m.as_str().to_string());
+        debug!("Found {} classes in {}", classes.len(), file_path.display());
+        Ok(classes)
+    }
+    
+    /// Scan already loaded text, attributing the classes to `file_path`
+    pub fn scan_content(&self, content: &str, file_path: &Path) -> Vec<GameClass> {
+        let tokens = tokenize(content);
+        let mut classes: Vec<GameClass> = Vec::new();
+        // One entry per open brace, with the index of the class it belongs to
+        let mut open: Vec<Option<usize>> = Vec::new();
+        let mut i = 0;
+        
+        while i < tokens.len() {
+            let token = &tokens[i];
+            // Macro calls are often written without a trailing `;`
+            let statement_start = i == 0 || matches!(tokens[i - 1].kind, TokenKind::Punct('{' | '}' | ';' | ')'));
+            let container = open.iter().rev().find_map(|entry| *entry);
             
-            trace!("Found class: {} (parent: {:?})", name, parent);
+            if token.is_punct('{') {
+                open.push(None);
+            } else if token.is_punct('}') {
+                open.pop();
+            } else if let Some((class, next)) = class_statement(&tokens, i, content, file_path) {
+                trace!("Found class: {} (parent: {:?})", class.name, class.parent);
+                
+                let mut class = class;
+                class.container_class = container.map(|index| classes[index].name.clone());
+                if class.kind == ClassKind::Definition {
+                    open.push(Some(classes.len()));
+                }
+                classes.push(class);
+                i = next;
+                continue;
+            } else if let (true, Some(index)) = (statement_start, container) {
+                if let Some((property, next)) = simple_property(&tokens, i, content, file_path) {
+                    classes[index].properties.push(property);
+                    i = next;
+                    continue;
+                }
+            }
             
-            classes.push(GameClass {
-                name,
-                parent,
-                file_path: file_path.to_path_buf(),
-                container_class: None,
-                properties: Vec::new(),
-            });
+            i += 1;
         }
         
-        debug!("Found {} classes in {}", classes.len(), file_path.display());
         classes
     }
     
+    /// Scan the `name = value;` statements outside of any class, e.g. of a `mod.cpp`
+    ///
+    /// Like class properties, only plain string and number values are returned.
+    pub fn scan_properties(&self, content: &str, file_path: &Path) -> Vec<ClassProperty> {
+        let tokens = tokenize(content);
+        let mut properties = Vec::new();
+        let mut depth = 0usize;
+        let mut i = 0;
+        
+        while i < tokens.len() {
+            let token = &tokens[i];
+            let statement_start = i == 0 || matches!(tokens[i - 1].kind, TokenKind::Punct('{' | '}' | ';'));
+            
+            if token.is_punct('{') {
+                depth += 1;
+            } else if token.is_punct('}') {
+                depth = depth.saturating_sub(1);
+            } else if depth == 0 && statement_start {
+                if let Some((property, next)) = simple_property(&tokens, i, content, file_path) {
+                    properties.push(property);
+                    i = next;
+                    continue;
+                }
+            }
+            
+            i += 1;
+        }
+        
+        properties
+    }
+    
     pub fn scan_directory(&self, dir_path: &Path) -> Vec<GameClass> {
         debug!("Simple scanning directory: {}", dir_path.display());
         
@@ -127,6 +182,91 @@ impl SimpleClassScanner {
     }
 }
 
+/// Parse `class Name;`, `class Name: Parent {` or `delete Name;` at token `i`
+///
+/// Returns the class and the index of the token after the statement header.
+fn class_statement(tokens: &[Token], i: usize, content: &str, file_path: &Path) -> Option<(GameClass, usize)> {
+    let keyword = tokens[i].word(content)?;
+    let name = tokens.get(i + 1)?.word(content)?.to_string();
+    
+    let (parent, end) = if keyword == "delete" {
+        tokens.get(i + 2).filter(|t| t.is_punct(';'))?;
+        (None, i + 2)
+    } else if keyword == "class" {
+        match tokens.get(i + 2) {
+            Some(t) if t.is_punct(':') => (Some(tokens.get(i + 3)?.word(content)?.to_string()), i + 4),
+            _ => (None, i + 2),
+        }
+    } else {
+        return None;
+    };
+    
+    let terminator = tokens.get(end)?;
+    let kind = match (keyword, &terminator.kind) {
+        ("delete", _) => ClassKind::Deleted,
+        (_, TokenKind::Punct('{')) => ClassKind::Definition,
+        (_, TokenKind::Punct(';')) => ClassKind::External,
+        _ => return None,
+    };
+    
+    let class = GameClass {
+        name,
+        parent,
+        file_path: file_path.to_path_buf(),
+        container_class: None,
+        properties: Vec::new(),
+        span: Some(span_at(content, file_path, tokens[i].start, terminator.end)),
+        kind,
+        fidelity: Fidelity::Low,
+    };
+    Some((class, end + 1))
+}
+
+/// Parse `name = value;` at token `i` if the value is a single string or number
+///
+/// Returns the property and the index of the token after the `;`.
+fn simple_property(tokens: &[Token], i: usize, content: &str, file_path: &Path) -> Option<(ClassProperty, usize)> {
+    let name = tokens[i].word(content)?;
+    let equals = tokens.get(i + 1).filter(|t| t.is_punct('='))?;
+    
+    let end = i + 2 + tokens[i + 2..].iter().position(|t| matches!(t.kind, TokenKind::Punct(';' | '{' | '}')))?;
+    if !tokens[end].is_punct(';') {
+        return None;
+    }
+    
+    let value = match &tokens[i + 2..end] {
+        [Token { kind: TokenKind::Str(value), .. }] => PropertyValue::String(value.clone()),
+        _ => {
+            let text = content[equals.end..tokens[end].start].trim();
+            if let Ok(number) = text.parse::<i64>() {
+                PropertyValue::Number(number)
+            } else if let Ok(number) = text.parse::<f64>() {
+                PropertyValue::Float(number)
+            } else {
+                return None;
+            }
+        }
+    };
+    
+    let mut property = ClassProperty::new(name.to_string(), value);
+    property.span = Some(span_at(content, file_path, tokens[i].start, tokens[i].end));
+    Some((property, end + 1))
+}
+
+/// Build a source span for a byte range of `content`
+fn span_at(content: &str, file_path: &Path, start: usize, end: usize) -> SourceSpan {
+    let before = &content[..start];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    
+    SourceSpan {
+        file: file_path.to_path_buf(),
+        line: before.matches('\n').count() + 1,
+        column: start - line_start + 1,
+        start,
+        end,
+    }
+}
+
 /// Parse a directory and return a ScanResult
 pub fn parse_directory(dir_path: &Path) -> ScanResult {
     debug!("Starting simple directory scanning at: {}", dir_path.display());
@@ -190,6 +330,12 @@ class Rifleman : BaseMan {
         assert!(classes[0].parent.is_none());
         assert_eq!(classes[1].name, "Rifleman");
         assert_eq!(classes[1].parent.as_deref(), Some("BaseMan"));
+        
+        // Spans point at the `class` keyword
+        let span = classes[1].span.as_ref().unwrap();
+        assert_eq!(span.line, 5);
+        assert_eq!(span.column, 1);
+        assert!(content[span.start..].starts_with("class Rifleman"));
     }
     
     #[test]
@@ -229,27 +375,64 @@ class Truck : Vehicle {};
             println!("  {}. {} (parent: {:?})", i+1, class.name, class.parent);
         }
         
-        assert_eq!(classes.len(), 5);
+        assert_eq!(classes.len(), 4);
         
         // Check Vehicle forward declaration
         assert_eq!(classes[0].name, "Vehicle");
         assert!(classes[0].parent.is_none());
+        assert_eq!(classes[0].kind, ClassKind::External);
         
-        // Check the Fake1 class in the comment - it's actually matched
-        assert_eq!(classes[1].name, "Fake1");
-        assert_eq!(classes[1].parent.as_deref(), Some("NotReal"));
+        // The Fake1 class in the comment is skipped
+        assert!(!classes.iter().any(|c| c.name == "Fake1"));
         
         // Check Car
-        assert_eq!(classes[2].name, "Car");
-        assert_eq!(classes[2].parent.as_deref(), Some("Vehicle"));
+        assert_eq!(classes[1].name, "Car");
+        assert_eq!(classes[1].parent.as_deref(), Some("Vehicle"));
+        assert_eq!(classes[1].properties.len(), 1);
+        assert_eq!(classes[1].properties[0].value.as_string(), Some("V8"));
         
         // Check Wheel (nested class)
-        assert_eq!(classes[3].name, "Wheel");
-        assert!(classes[3].parent.is_none());
+        assert_eq!(classes[2].name, "Wheel");
+        assert!(classes[2].parent.is_none());
+        assert_eq!(classes[2].container_class.as_deref(), Some("Car"));
+        assert!(matches!(classes[2].properties[0].value, PropertyValue::Number(17)));
         
         // Check Truck
-        assert_eq!(classes[4].name, "Truck");
-        assert_eq!(classes[4].parent.as_deref(), Some("Vehicle"));
+        assert_eq!(classes[3].name, "Truck");
+        assert_eq!(classes[3].parent.as_deref(), Some("Vehicle"));
+        assert!(classes[3].container_class.is_none());
+    }
+    
+    #[test]
+    fn test_same_line_classes_and_macros() {
+        let content = r#"class CfgPatches { class my_addon { units[] = {"A", "B"}; requiredVersion = 2.06; name = QUOTE(COMPONENT); }; };
+class CfgVehicles{ class Car; class Tank: Car{ MACRO_CREW(class Crew {};) armor = -0.5; }; delete Old; };"#;
+        
+        let classes = SimpleClassScanner::new().scan_content(content, Path::new("config.cpp"));
+        let names: Vec<_> = classes.iter().map(|c| (c.name.as_str(), c.container_class.as_deref())).collect();
+        assert_eq!(names, vec![
+            ("CfgPatches", None),
+            ("my_addon", Some("CfgPatches")),
+            ("CfgVehicles", None),
+            ("Car", Some("CfgVehicles")),
+            ("Tank", Some("CfgVehicles")),
+            ("Crew", Some("Tank")),
+            ("Old", Some("CfgVehicles")),
+        ]);
+        
+        // Arrays and macro values are skipped, plain values are kept
+        let my_addon = &classes[1];
+        assert_eq!(my_addon.properties.len(), 1);
+        assert_eq!(my_addon.properties[0].name, "requiredVersion");
+        assert!(matches!(my_addon.properties[0].value, PropertyValue::Float(v) if v == 2.06));
+        
+        // Braces inside macro arguments don't close the class
+        let tank = &classes[4];
+        assert_eq!(tank.properties.len(), 1);
+        assert!(matches!(tank.properties[0].value, PropertyValue::Float(v) if v == -0.5));
+        
+        assert_eq!(classes[6].kind, ClassKind::Deleted);
+        assert!(classes.iter().all(|c| c.fidelity == Fidelity::Low));
     }
     
     #[test]
@@ -337,4 +520,40 @@ class CfgWeapons
                        "ItemInfo should inherit from UniformItem");
         }
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn test_scan_top_level_properties() {
+        let content = r#"
+name = "Mod; with = signs";
+/* picture = "commented.paa"; */
+author = // comments between tokens
+    "Someone";
+tags[] = {"a", "b"};
+class Extra { hidden = 1; };
+publishedid = 450814997; overview = "Say ""hi""";
+"#;
+        
+        let properties = SimpleClassScanner::new().scan_properties(content, Path::new("mod.cpp"));
+        let values: Vec<(&str, String)> = properties.iter()
+            .map(|p| (p.name.as_str(), format!("{:?}", p.value)))
+            .collect();
+        assert_eq!(values, vec![
+            ("name", format!("{:?}", PropertyValue::String("Mod; with = signs".to_string()))),
+            ("author", format!("{:?}", PropertyValue::String("Someone".to_string()))),
+            ("publishedid", format!("{:?}", PropertyValue::Number(450814997))),
+            ("overview", format!("{:?}", PropertyValue::String("Say \"hi\"".to_string()))),
+        ]);
+        assert_eq!(properties[1].span.as_ref().unwrap().line, 4);
+    }
+    
+    #[test]
+    fn test_missing_file_reports_io_error() {
+        let scanner = SimpleClassScanner::new();
+        let missing = Path::new("does/not/exist.hpp");
+        
+        let errors = scanner.try_scan_file(missing).unwrap_err();
+        assert!(matches!(errors[..], [ScanError::Io(_)]));
+        assert_eq!(errors[0].file(), missing);
+        assert!(scanner.scan_file(missing).is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::Path;
 use std::time::Instant;
-use gamedata_scanner_models::{Scanner, ScanResult, GameClass};
+use gamedata_scanner_models::{Scanner, ScanResult, GameClass, ScanError};
 use crate::SimpleClassScanner;
 
 /// Simple scanner implementation 
@@ -40,10 +40,8 @@ impl Scanner for SimpleScanner {
         Ok(result)
     }
     
-    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, String> {
+    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
         let scanner = SimpleClassScanner::new();
-        let classes = scanner.scan_file(file_path);
-        
-        Ok(classes)
+        scanner.try_scan_file(file_path)
     }
 } 
\ No newline at end of file
@@ -0,0 +1,149 @@
+//! Lightweight tokenizer for config files
+//!
+//! Splits unpreprocessed text into words, string literals and punctuation,
+//! dropping whitespace, comments and preprocessor directives. Macros are not
+//! expanded, their names and arguments come through as ordinary tokens.
+
+/// What kind of text a token covers
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// Identifier, keyword or number, e.g. `class`, `scope`, `1.5`
+    Word,
+    /// String literal with `""` escapes resolved
+    Str(String),
+    /// Any other single character, e.g. `{`, `;`, `=`
+    Punct(char),
+}
+
+/// A token with its byte range in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    /// Check if the token is the given punctuation character
+    pub fn is_punct(&self, c: char) -> bool {
+        self.kind == TokenKind::Punct(c)
+    }
+
+    /// Text of a word token, `None` for strings and punctuation
+    pub fn word<'a>(&self, content: &'a str) -> Option<&'a str> {
+        match self.kind {
+            TokenKind::Word => Some(&content[self.start..self.end]),
+            _ => None,
+        }
+    }
+}
+
+/// Split `content` into tokens
+pub fn tokenize(content: &str) -> Vec<Token> {
+    let bytes = content.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line_start = true;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+
+        if c == b'\n' {
+            line_start = true;
+            i += 1;
+        } else if c.is_ascii_whitespace() {
+            i += 1;
+        } else if bytes[i..].starts_with(b"//") {
+            i = find_byte(bytes, i, b'\n');
+        } else if bytes[i..].starts_with(b"/*") {
+            i = content[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+        } else if c == b'#' && line_start {
+            // Directives run to the end of the line, `\` continues them
+            loop {
+                i = find_byte(bytes, i, b'\n');
+                if i >= bytes.len() || !content[..i].trim_end_matches('\r').ends_with('\\') {
+                    break;
+                }
+                i += 1;
+            }
+        } else if c == b'"' {
+            let start = i;
+            let mut value = String::new();
+            i += 1;
+            while i < bytes.len() {
+                if bytes[i] == b'"' {
+                    if bytes.get(i + 1) == Some(&b'"') {
+                        value.push('"');
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                // An unterminated string ends at the line break
+                if bytes[i] == b'\n' {
+                    break;
+                }
+                let ch = content[i..].chars().next().unwrap();
+                value.push(ch);
+                i += ch.len_utf8();
+            }
+            tokens.push(Token { kind: TokenKind::Str(value), start, end: i });
+            line_start = false;
+        } else if is_word_byte(c) {
+            let start = i;
+            while i < bytes.len() && is_word_byte(bytes[i]) {
+                i += 1;
+            }
+            tokens.push(Token { kind: TokenKind::Word, start, end: i });
+            line_start = false;
+        } else {
+            let ch = content[i..].chars().next().unwrap();
+            tokens.push(Token { kind: TokenKind::Punct(ch), start: i, end: i + ch.len_utf8() });
+            i += ch.len_utf8();
+            line_start = false;
+        }
+    }
+
+    tokens
+}
+
+/// Index of the next `byte` at or after `from`, or the end of the input
+fn find_byte(bytes: &[u8], from: usize, byte: u8) -> usize {
+    bytes[from..].iter().position(|&b| b == byte).map_or(bytes.len(), |pos| from + pos)
+}
+
+fn is_word_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'.'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(content: &str) -> Vec<String> {
+        tokenize(content)
+            .iter()
+            .map(|token| match &token.kind {
+                TokenKind::Word => content[token.start..token.end].to_string(),
+                TokenKind::Str(value) => format!("{:?}", value),
+                TokenKind::Punct(c) => c.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_skips_comments_and_directives() {
+        let content = "#define FOO(a) \\\n    class a {};\n// class Hidden;\n/* class\nHidden2; */ class A{x=1.5;};";
+        assert_eq!(kinds(content), ["class", "A", "{", "x", "=", "1.5", ";", "}", ";"]);
+    }
+
+    #[test]
+    fn test_strings() {
+        let content = "text = \"say \"\"class\"\" // here\";";
+        assert_eq!(kinds(content), ["text", "=", "\"say \\\"class\\\" // here\"", ";"]);
+
+        // Unterminated strings stop at the end of the line
+        assert_eq!(kinds("a = \"open\nclass B;"), ["a", "=", "\"open\"", "class", "B", ";"]);
+    }
+}
@@ -0,0 +1,318 @@
+//! Reader for PBO archives
+//!
+//! Addons are shipped as PBO files: a header listing every entry, followed
+//! by the entry data in the same order. The first header entry may carry
+//! key/value properties such as the `prefix` the addon is mounted under.
+//! Entries are either stored as is or packed with LZSS (`Cprs`).
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use log::debug;
+
+mod lzss;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
+/// Packing method of the properties entry, `Vers`
+const METHOD_VERSION: u32 = 0x5665_7273;
+
+/// Packing method of LZSS packed entries, `Cprs`
+const METHOD_COMPRESSED: u32 = 0x4370_7273;
+
+/// Longest entry name or property accepted before the header is considered corrupt
+const MAX_STRING_LEN: usize = 1024;
+
+/// A file stored in a PBO archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PboEntry {
+    /// Path of the entry inside the archive, with `\` separators as stored
+    pub name: String,
+    /// Packing method, 0 for stored entries
+    pub packing: u32,
+    /// Unpacked size, 0 for most stored entries
+    pub original_size: u32,
+    /// Modification time as a Unix timestamp
+    pub timestamp: u32,
+    /// Size of the data in the archive
+    pub data_size: u32,
+    /// Offset of the data from the start of the archive
+    offset: u64,
+}
+
+impl PboEntry {
+    /// Check if the entry is LZSS packed
+    pub fn is_compressed(&self) -> bool {
+        self.packing == METHOD_COMPRESSED
+    }
+
+    /// Size of the entry once unpacked
+    pub fn size(&self) -> u32 {
+        if self.is_compressed() || self.original_size != 0 {
+            self.original_size
+        } else {
+            self.data_size
+        }
+    }
+
+    /// Entry name with `/` separators
+    pub fn normalized_name(&self) -> String {
+        self.name.replace('\\', "/")
+    }
+
+    /// Lowercase extension of the entry, if any
+    pub fn extension(&self) -> Option<String> {
+        Path::new(&self.normalized_name())
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+    }
+
+    /// Relative path to extract the entry to
+    ///
+    /// Returns `None` for names that would leave the extraction directory,
+    /// e.g. absolute paths or `..` components.
+    pub fn relative_path(&self) -> Option<PathBuf> {
+        let path = PathBuf::from(self.normalized_name());
+        let safe = path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        (safe && path.file_name().is_some()).then_some(path)
+    }
+}
+
+/// An open PBO archive
+#[derive(Debug, Clone)]
+pub struct PboArchive {
+    path: PathBuf,
+    properties: Vec<(String, String)>,
+    entries: Vec<PboEntry>,
+}
+
+impl PboArchive {
+    /// Read the header of the archive at `path`
+    ///
+    /// Entry data is only read on demand by [`PboArchive::read`].
+    pub fn open(path: &Path) -> io::Result<Self> {
+        debug!("Reading PBO header: {}", path.display());
+        let file_len = fs::metadata(path)?.len();
+        let mut reader = BufReader::new(File::open(path)?);
+        let Header { properties, mut entries } = read_header(&mut reader)?;
+
+        let mut offset = reader.stream_position()?;
+        for entry in &mut entries {
+            entry.offset = offset;
+            offset += entry.data_size as u64;
+        }
+        if offset > file_len {
+            return Err(invalid(format!("Entry data ends at {} but the archive has {} bytes", offset, file_len)));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            properties,
+            entries,
+        })
+    }
+
+    /// Path the archive was opened from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Header properties in the order they are stored
+    pub fn properties(&self) -> &[(String, String)] {
+        &self.properties
+    }
+
+    /// Value of a header property, matched case-insensitively
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Mount prefix from the header, without leading or trailing separators
+    pub fn prefix(&self) -> Option<&str> {
+        self.property("prefix")
+            .map(|prefix| prefix.trim_matches(|c| c == '\\' || c == '/'))
+            .filter(|prefix| !prefix.is_empty())
+    }
+
+    /// All entries in the archive
+    pub fn entries(&self) -> &[PboEntry] {
+        &self.entries
+    }
+
+    /// Look up an entry by name, ignoring case and separator style
+    pub fn entry(&self, name: &str) -> Option<&PboEntry> {
+        let name = name.replace('/', "\\");
+        self.entries.iter().find(|entry| entry.name.eq_ignore_ascii_case(&name))
+    }
+
+    /// Read and unpack the data of an entry
+    pub fn read(&self, entry: &PboEntry) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0; entry.data_size as usize];
+        file.read_exact(&mut data)?;
+
+        match entry.packing {
+            0 => Ok(data),
+            METHOD_COMPRESSED => lzss::decompress(&data, entry.original_size as usize)
+                .map_err(|e| invalid(format!("Cannot unpack `{}`: {}", entry.name, e))),
+            method => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unsupported packing method {:#010x} for `{}`", method, entry.name),
+            )),
+        }
+    }
+
+    /// Extract the entries accepted by `filter` below `dir`, keeping their paths
+    ///
+    /// Entries with unsafe names are skipped. Returns the entries and the
+    /// paths they were written to.
+    pub fn extract<F>(&self, dir: &Path, filter: F) -> io::Result<Vec<(PboEntry, PathBuf)>>
+    where
+        F: Fn(&PboEntry) -> bool,
+    {
+        let mut extracted = Vec::new();
+        for entry in self.entries.iter().filter(|entry| filter(entry)) {
+            let Some(relative) = entry.relative_path() else {
+                debug!("Skipping unsafe PBO entry name: {}", entry.name);
+                continue;
+            };
+
+            let target = dir.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, self.read(entry)?)?;
+            extracted.push((entry.clone(), target));
+        }
+        Ok(extracted)
+    }
+}
+
+/// Check if `path` has a `.pbo` extension
+pub fn is_pbo_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pbo"))
+}
+
+struct Header {
+    properties: Vec<(String, String)>,
+    entries: Vec<PboEntry>,
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> io::Result<Header> {
+    let mut properties = Vec::new();
+    let mut entries = Vec::new();
+
+    loop {
+        let name = read_asciiz(reader)?;
+        let packing = read_u32(reader)?;
+        let original_size = read_u32(reader)?;
+        let _reserved = read_u32(reader)?;
+        let timestamp = read_u32(reader)?;
+        let data_size = read_u32(reader)?;
+
+        if name.is_empty() {
+            if packing != METHOD_VERSION {
+                break;
+            }
+            loop {
+                let key = read_asciiz(reader)?;
+                if key.is_empty() {
+                    break;
+                }
+                let value = read_asciiz(reader)?;
+                properties.push((key, value));
+            }
+            continue;
+        }
+
+        entries.push(PboEntry {
+            name,
+            packing,
+            original_size,
+            timestamp,
+            data_size,
+            offset: 0,
+        });
+    }
+
+    Ok(Header { properties, entries })
+}
+
+fn read_asciiz<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    reader.take(MAX_STRING_LEN as u64 + 1).read_until(0, &mut bytes)?;
+    match bytes.pop() {
+        Some(0) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        _ if bytes.len() >= MAX_STRING_LEN => Err(invalid("Header string is too long".to_string())),
+        _ => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Header ends early")),
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use crate::test_support::build_pbo;
+
+    #[test]
+    fn test_read_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("weapons.pbo");
+        let packed = [0b0000_0111, b'a', b'b', b'c', 3, 0x03];
+        fs::write(&path, build_pbo("\\x\\mod\\addons\\weapons\\", &[
+            ("config.cpp", 0, 0, b"class CfgPatches {};"),
+            ("data\\packed.txt", METHOD_COMPRESSED, 9, &packed),
+        ])).unwrap();
+
+        let pbo = PboArchive::open(&path).unwrap();
+        assert_eq!(pbo.prefix(), Some("x\\mod\\addons\\weapons"));
+        assert_eq!(pbo.entries().len(), 2);
+
+        let config = pbo.entry("CONFIG.CPP").unwrap();
+        assert_eq!(config.size(), 20);
+        assert_eq!(pbo.read(config).unwrap(), b"class CfgPatches {};");
+
+        let packed = pbo.entry("data/packed.txt").unwrap();
+        assert!(packed.is_compressed());
+        assert_eq!(packed.extension().as_deref(), Some("txt"));
+        assert_eq!(pbo.read(packed).unwrap(), b"abcabcabc");
+
+        let out = dir.path().join("out");
+        let extracted = pbo.extract(&out, |entry| entry.extension().as_deref() == Some("txt")).unwrap();
+        assert_eq!(extracted.len(), 1);
+        assert_eq!(extracted[0].1, out.join("data").join("packed.txt"));
+        assert_eq!(fs::read(&extracted[0].1).unwrap(), b"abcabcabc");
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_unsafe_archives() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("broken.pbo");
+        let mut data = build_pbo("broken", &[("config.cpp", 0, 0, b"class A {};")]);
+        data.truncate(data.len() - 4);
+        fs::write(&path, &data).unwrap();
+        assert!(PboArchive::open(&path).is_err());
+
+        fs::write(&path, &data[..10]).unwrap();
+        assert!(PboArchive::open(&path).is_err());
+
+        fs::write(&path, build_pbo("evil", &[("..\\..\\evil.cpp", 0, 0, b"class A {};")])).unwrap();
+        let pbo = PboArchive::open(&path).unwrap();
+        assert_eq!(pbo.entries()[0].relative_path(), None);
+        assert!(pbo.extract(&dir.path().join("out"), |_| true).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+//! LZSS decompression for `Cprs` packed PBO entries
+//!
+//! Each flag byte describes the next eight blocks, least significant bit
+//! first: a set bit is a literal byte, a clear bit a two-byte back reference
+//! with a 12-bit distance and a 4-bit length (plus 3). References before the
+//! start of the output produce spaces. The packed data ends with a `u32` sum
+//! of all unpacked bytes.
+
+use std::io;
+
+/// Most bytes one packed byte can unpack to: a flag byte and eight
+/// references of two bytes each produce 8 * 18 bytes from 17
+const MAX_RATIO: usize = 9;
+
+/// Unpack `data` into exactly `size` bytes, verifying the trailing checksum if present
+///
+/// `size` comes from the archive header, so it is checked against what
+/// `data` could possibly unpack to before anything is allocated.
+pub fn decompress(data: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    if size > data.len().saturating_mul(MAX_RATIO) {
+        return Err(invalid("Unpacked size is too large for the packed data"));
+    }
+    let mut out = Vec::with_capacity(size);
+    let mut pos = 0;
+
+    let next = |pos: &mut usize| -> io::Result<u8> {
+        let byte = data.get(*pos).copied().ok_or_else(|| invalid("Packed data ends early"))?;
+        *pos += 1;
+        Ok(byte)
+    };
+
+    while out.len() < size {
+        let flags = next(&mut pos)?;
+        for bit in 0..8 {
+            if out.len() >= size {
+                break;
+            }
+
+            if flags & (1 << bit) != 0 {
+                out.push(next(&mut pos)?);
+                continue;
+            }
+
+            let low = next(&mut pos)? as usize;
+            let high = next(&mut pos)? as usize;
+            let distance = low | ((high & 0xf0) << 4);
+            let length = (high & 0x0f) + 3;
+            if distance == 0 {
+                return Err(invalid("Back reference with distance 0 in packed data"));
+            }
+
+            // Byte by byte, the reference may overlap the bytes it produces
+            for _ in 0..length.min(size - out.len()) {
+                let byte = match out.len().checked_sub(distance) {
+                    Some(from) => out[from],
+                    None => b' ',
+                };
+                out.push(byte);
+            }
+        }
+    }
+
+    if let Some(checksum) = data.get(pos..pos + 4) {
+        let expected = u32::from_le_bytes(checksum.try_into().unwrap());
+        let actual = out.iter().fold(0u32, |sum, &b| sum.wrapping_add(b as u32));
+        if expected != actual {
+            return Err(invalid("Checksum mismatch in packed data"));
+        }
+    }
+
+    Ok(out)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        // "abc", then 6 bytes from 3 back, then one reference before the start
+        let mut packed = vec![0b0000_0111, b'a', b'b', b'c', 3, 0x03, 20, 0x00];
+        let expected = b"abcabcabc   ";
+        let sum = expected.iter().map(|&b| b as u32).sum::<u32>();
+        packed.extend_from_slice(&sum.to_le_bytes());
+
+        assert_eq!(decompress(&packed, expected.len()).unwrap(), expected);
+
+        packed.pop();
+        packed.push(0xff);
+        assert_eq!(decompress(&packed, expected.len()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(decompress(&packed[..5], expected.len()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_corrupt_data() {
+        // A back reference with distance 0 would copy the byte being written
+        let packed = [0b0000_0001, b'a', 0, 0x00];
+        assert_eq!(decompress(&packed, 4).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // A header claiming a huge unpacked size fails before allocating
+        let packed = [0b0000_0001, b'a'];
+        assert_eq!(decompress(&packed, usize::MAX).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
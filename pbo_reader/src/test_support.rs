@@ -0,0 +1,34 @@
+//! Builders for PBO fixtures, shared by the tests of this and dependent crates
+//!
+//! Enabled by the `test-support` feature.
+
+use crate::METHOD_VERSION;
+
+/// Build a PBO with a `prefix` header property and the given entries
+///
+/// Each entry is `(name, packing, original_size, data)`; use `0, 0` for
+/// stored entries. `data` is written as is, so packed entries must already
+/// be LZSS packed.
+pub fn build_pbo(prefix: &str, entries: &[(&str, u32, u32, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    header(&mut out, "", [METHOD_VERSION, 0, 0, 0, 0]);
+    out.extend_from_slice(b"prefix\0");
+    out.extend_from_slice(prefix.as_bytes());
+    out.extend_from_slice(b"\0\0");
+    for (name, packing, original_size, data) in entries {
+        header(&mut out, name, [*packing, *original_size, 0, 0, data.len() as u32]);
+    }
+    header(&mut out, "", [0; 5]);
+    for (_, _, _, data) in entries {
+        out.extend_from_slice(data);
+    }
+    out
+}
+
+fn header(out: &mut Vec<u8>, name: &str, values: [u32; 5]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    for value in values {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
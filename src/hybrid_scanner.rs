@@ -0,0 +1,157 @@
+//! Scanner that falls back to the simple parser per file
+//!
+//! The advanced parser extracts properties, but gives up on files that use
+//! macros it can't expand or that don't parse. The simple parser still finds
+//! the classes in those files, so every class ends up in the result, with the
+//! fallback classes marked [`Fidelity::Low`].
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use log::debug;
+use walkdir::WalkDir;
+use gamedata_scanner_models::{Fidelity, GameClass, Scanner, ScanError, ScanResult};
+use parser_advanced::scanner::AdvancedScanner;
+use parser_simple::SimpleClassScanner;
+
+use crate::scanner::TEXT_EXTENSIONS;
+
+/// Hybrid scanner implementation
+pub struct HybridScanner {
+    advanced: AdvancedScanner,
+    simple: SimpleClassScanner,
+}
+
+impl HybridScanner {
+    /// Create a new hybrid scanner
+    pub fn new() -> Self {
+        Self {
+            advanced: AdvancedScanner::new(),
+            simple: SimpleClassScanner::new(),
+        }
+    }
+}
+
+impl Default for HybridScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scanner for HybridScanner {
+    fn scan_directory(&self, dir_path: &Path) -> Result<ScanResult, String> {
+        let start_time = Instant::now();
+
+        if !dir_path.is_dir() {
+            return Err(format!("Directory does not exist: {}", dir_path.display()));
+        }
+
+        let files: Vec<PathBuf> = WalkDir::new(dir_path)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .filter(|path| is_config_file(path))
+            .collect();
+
+        let mut result = ScanResult::new();
+        result.files_scanned = files.len();
+
+        for file_path in &files {
+            match self.parse_file(file_path) {
+                Ok(file_classes) => result.add_classes(file_classes),
+                Err(_) => result.files_with_errors += 1,
+            }
+        }
+
+        result.scan_time_ms = Some(start_time.elapsed().as_millis() as u64);
+
+        Ok(result)
+    }
+
+    fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
+        let errors = match self.advanced.parse_file(file_path) {
+            Ok(classes) => return Ok(classes),
+            Err(errors) => errors,
+        };
+
+        // Only fall back when the text could be read but not understood
+        if !errors.iter().all(|error| matches!(error, ScanError::Preprocess(_) | ScanError::Parse(_))) {
+            return Err(errors);
+        }
+
+        debug!("Falling back to simple parser for {}: {} errors", file_path.display(), errors.len());
+        let mut classes = self.simple.try_scan_file(file_path)?;
+        for class in &mut classes {
+            class.fidelity = Fidelity::Low;
+        }
+        Ok(classes)
+    }
+}
+
+/// Check if a file is a text config, by the extensions the scanner reads by default
+fn is_config_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| TEXT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_falls_back_on_unexpandable_macros() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("config.cpp");
+        fs::write(&file_path, r#"
+            class CfgWeapons {
+                class Rifle {
+                    displayName = "Rifle";
+                    UNKNOWN_MACRO(scope = 2)
+                };
+            };
+        "#).unwrap();
+
+        let scanner = HybridScanner::new();
+        assert!(AdvancedScanner::new().parse_file(&file_path).is_err());
+
+        let classes = scanner.parse_file(&file_path).unwrap();
+        let names: Vec<_> = classes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["CfgWeapons", "Rifle"]);
+        assert!(classes.iter().all(GameClass::is_low_fidelity));
+
+        // Plain values survive, the unexpanded macro is skipped
+        let rifle = &classes[1];
+        assert_eq!(rifle.properties.len(), 1);
+        assert_eq!(rifle.properties[0].value.as_string(), Some("Rifle"));
+    }
+
+    #[test]
+    fn test_keeps_full_parse_when_possible() {
+        let temp_dir = TempDir::new().unwrap();
+        let good = temp_dir.path().join("good.hpp");
+        fs::write(&good, "class Car { speed = 100; };").unwrap();
+        let bad = temp_dir.path().join("bad.hpp");
+        fs::write(&bad, "class Tank { speed = ; };").unwrap();
+        fs::write(temp_dir.path().join("description.ext"), "class CfgDebriefing { class End1 {}; };").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "class Ignored {};").unwrap();
+
+        let result = HybridScanner::new().scan_directory(temp_dir.path()).unwrap();
+        assert_eq!(result.files_scanned, 3);
+        assert!(result.class_map.contains_key("CfgDebriefing"));
+        assert_eq!(result.files_with_errors, 0);
+
+        let car = &result.class_map["Car"][0];
+        assert_eq!(car.fidelity, Fidelity::Full);
+        assert_eq!(car.properties.len(), 1);
+        assert!(result.class_map["Tank"][0].is_low_fidelity());
+    }
+
+    #[test]
+    fn test_missing_file_is_not_a_fallback() {
+        let errors = HybridScanner::new().parse_file(Path::new("does/not/exist.cpp")).unwrap_err();
+        assert!(matches!(errors[0], ScanError::Io(_)));
+    }
+}
@@ -31,15 +31,30 @@ use std::path::Path;
 
 // Re-export models from models
 pub use gamedata_scanner_models::{
-    GameClass, ClassProperty, PropertyValue, Scanner as ClassScanner, 
-    ScanResult, FileParser
+    GameClass, ClassKind, Fidelity, ClassProperty, PropertyOperator, PropertyValue, ArrayElement, Scanner as ClassScanner, 
+    ScanResult, FileParser, ConfigTree, ConfigNode, ConfigTreeError, EffectiveProperty,
+    LoadOrder, LoadOrderError, ParsedFile, ParseStatus, PreprocessorInfo, Diagnostic, ScanError, Severity,
+    write_class, write_classes, WriteError, WriterOptions
 };
-pub use scanner_factory::{get_scanner, get_parser};
-pub use scanner::ScannerConfig;
+pub use scanner_factory::{get_scanner, get_hybrid_scanner, get_parser};
+pub use hybrid_scanner::HybridScanner;
+pub use parser_registry::{ParserCapabilities, ParserEntry, ParserRegistry};
+pub use scanner::{include_options, ScannerConfig};
 pub use scanner::ScannerResult;
+pub use virtual_paths::{AddonInfo, VirtualPathMap};
+pub use mods::{ModInfo, ModMap};
+pub use parser_advanced::{parse_file_detailed, parse_file_recovering, preprocess_file, ParseOptions, PreprocessedOutput, VirtualIncludeRoot};
+pub use parser_rapified::{rapify, RapifyError};
+pub use pbo_archives::{archive_entry_path, archive_root};
+pub use pbo_reader::{PboArchive, PboEntry};
 
 pub mod scanner_factory;
+pub mod hybrid_scanner;
+pub mod mods;
+pub mod parser_registry;
+pub mod pbo_archives;
 pub mod scanner;
+pub mod virtual_paths;
 
 /// Main scanner interface for processing game data files
 #[derive(Debug, Clone)]
@@ -0,0 +1,204 @@
+//! Mod folders found while scanning
+//!
+//! Arma loads content as mods: an `@Name` folder with an `addons` directory of
+//! PBOs (or loose addon folders) and optional `mod.cpp`/`meta.cpp` metadata
+//! with the display name, author and Steam Workshop id. [`ModMap`] records the
+//! mods of a scanned tree so results can be attributed and grouped by mod.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::debug;
+use parser_simple::SimpleClassScanner;
+use serde::{Deserialize, Serialize};
+use gamedata_scanner_models::PropertyValue;
+
+use crate::virtual_paths::VirtualPathMap;
+
+/// File names (lowercase) of the metadata files in a mod folder
+const METADATA_FILES: &[&str] = &["mod.cpp", "meta.cpp"];
+
+/// A mod folder (`@CBA_A3`) found while walking a directory tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModInfo {
+    /// Display name from `mod.cpp` or `meta.cpp`, or the folder name
+    pub name: String,
+    /// Mod folder on disk
+    pub path: PathBuf,
+    /// Steam Workshop id (`publishedid` in `meta.cpp`), `None` for local mods
+    pub workshop_id: Option<u64>,
+    /// Author from `mod.cpp` or `meta.cpp`
+    pub author: Option<String>,
+    /// Prefixes of the addons in the mod, loose folders and PBOs alike
+    pub addons: Vec<String>,
+}
+
+/// Mod folders of a scanned tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModMap {
+    mods: Vec<ModInfo>,
+}
+
+impl ModMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether `dir` is a mod folder and record it
+    ///
+    /// A directory is a mod folder if it has an `addons` subdirectory and
+    /// either its name starts with `@` or it holds a `mod.cpp` or `meta.cpp`.
+    pub fn inspect_dir(&mut self, dir: &Path) -> Option<&ModInfo> {
+        if !dir.join("addons").is_dir() {
+            return None;
+        }
+
+        let folder_name = dir.file_name()?.to_string_lossy().to_string();
+        let mod_cpp = read_metadata_file(&dir.join("mod.cpp"));
+        let meta_cpp = read_metadata_file(&dir.join("meta.cpp"));
+        if !folder_name.starts_with('@') && mod_cpp.is_none() && meta_cpp.is_none() {
+            return None;
+        }
+
+        let value = |key: &str| {
+            mod_cpp.as_ref().and_then(|values| values.get(key))
+                .or_else(|| meta_cpp.as_ref().and_then(|values| values.get(key)))
+                .filter(|value| !value.is_empty())
+                .cloned()
+        };
+        let info = ModInfo {
+            name: value("name").unwrap_or(folder_name),
+            path: dir.to_path_buf(),
+            workshop_id: meta_cpp.as_ref()
+                .and_then(|values| values.get("publishedid"))
+                .and_then(|id| id.parse().ok())
+                .filter(|&id| id != 0),
+            author: value("author"),
+            addons: Vec::new(),
+        };
+
+        debug!("Found mod {} at {}", info.name, info.path.display());
+        self.mods.push(info);
+        self.mods.last()
+    }
+
+    /// All known mods
+    pub fn mods(&self) -> &[ModInfo] {
+        &self.mods
+    }
+
+    /// Whether no mods were found
+    pub fn is_empty(&self) -> bool {
+        self.mods.is_empty()
+    }
+
+    /// Find the mod that contains `path`, including entries of its PBOs
+    pub fn mod_for(&self, path: &Path) -> Option<&ModInfo> {
+        self.mods.iter()
+            .filter(|info| path.starts_with(&info.path))
+            .max_by_key(|info| info.path.components().count())
+    }
+
+    /// Whether `path` is the `mod.cpp` or `meta.cpp` of a known mod
+    pub fn is_metadata_file(&self, path: &Path) -> bool {
+        let is_metadata = path.file_name()
+            .is_some_and(|name| METADATA_FILES.contains(&name.to_string_lossy().to_lowercase().as_str()));
+        is_metadata && path.parent().is_some_and(|dir| self.mods.iter().any(|info| info.path == dir))
+    }
+
+    /// Fill in the addon lists from the addons found in the same tree
+    pub fn assign_addons(&mut self, addons: &VirtualPathMap) {
+        for addon in addons.addons() {
+            let owner = self.mods.iter()
+                .enumerate()
+                .filter(|(_, info)| addon.path.starts_with(&info.path))
+                .max_by_key(|(_, info)| info.path.components().count())
+                .map(|(index, _)| index);
+            if let Some(index) = owner {
+                self.mods[index].addons.push(addon.prefix.clone());
+            }
+        }
+    }
+}
+
+/// Read the top-level `key = value;` pairs of a `mod.cpp` or `meta.cpp`
+///
+/// Keys are lowercased. Only string and number values are kept, which is all
+/// these files normally have.
+fn read_metadata_file(path: &Path) -> Option<HashMap<String, String>> {
+    let content = fs::read_to_string(path).ok()?;
+
+    let values = SimpleClassScanner::new()
+        .scan_properties(&content, path)
+        .into_iter()
+        .filter_map(|property| {
+            let value = match property.value {
+                PropertyValue::String(value) => value,
+                PropertyValue::Number(value) => value.to_string(),
+                PropertyValue::Float(value) => value.to_string(),
+                _ => return None,
+            };
+            Some((property.name.to_lowercase(), value))
+        })
+        .collect();
+
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::virtual_paths::AddonInfo;
+
+    #[test]
+    fn test_inspect_mod_folders() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let cba = temp_dir.path().join("@CBA_A3");
+        fs::create_dir_all(cba.join("addons")).unwrap();
+        fs::write(cba.join("mod.cpp"), concat!(
+            "name = \"Community Base Addons v3.18\";\n",
+            "/* author = \"Commented out\"; */\n",
+            "author =\n    \"CBA Team\"; tooltip = \"CBA; \"\"the\"\" = framework\";\n",
+        )).unwrap();
+        fs::write(cba.join("meta.cpp"), "protocol = 1;\npublishedid = 450814997;\nname = \"CBA_A3\";\n").unwrap();
+
+        let local = temp_dir.path().join("@local");
+        fs::create_dir_all(local.join("addons")).unwrap();
+        fs::write(local.join("meta.cpp"), "publishedid = 0;\n").unwrap();
+
+        let not_a_mod = temp_dir.path().join("tools");
+        fs::create_dir_all(not_a_mod.join("addons")).unwrap();
+
+        let mut mods = ModMap::new();
+        for dir in [&cba, &local, &not_a_mod] {
+            mods.inspect_dir(dir);
+        }
+        assert_eq!(mods.mods().len(), 2);
+
+        let info = mods.mod_for(&cba.join("addons").join("main.pbo!").join("config.cpp")).unwrap();
+        assert_eq!(info.name, "Community Base Addons v3.18");
+        assert_eq!(info.workshop_id, Some(450814997));
+        assert_eq!(info.author.as_deref(), Some("CBA Team"));
+        assert_eq!(read_metadata_file(&cba.join("mod.cpp")).unwrap()["tooltip"], "CBA; \"the\" = framework");
+
+        let info = mods.mod_for(&local).unwrap();
+        assert_eq!(info.name, "@local");
+        assert_eq!(info.workshop_id, None);
+
+        assert!(mods.is_metadata_file(&cba.join("mod.cpp")));
+        assert!(!mods.is_metadata_file(&not_a_mod.join("mod.cpp")));
+
+        let mut addons = VirtualPathMap::new();
+        addons.insert(AddonInfo {
+            prefix: "x\\cba\\addons\\main".to_string(),
+            path: cba.join("addons").join("main.pbo!"),
+            has_prefix_file: true,
+        });
+        mods.assign_addons(&addons);
+        assert_eq!(mods.mods()[0].addons, vec!["x\\cba\\addons\\main"]);
+        assert!(mods.mods()[1].addons.is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! Named parser implementations
+//!
+//! Parsers register a [`ClassScanner`] and/or a [`FileParser`] under a name,
+//! together with what they can extract. [`ScannerConfig`](crate::ScannerConfig)
+//! and the batch tool pick a parser by that name, so other implementations can
+//! be added without changing the factory functions.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use gamedata_scanner_models::{FileParser, Scanner as ClassScanner};
+
+use crate::hybrid_scanner::HybridScanner;
+
+/// Name of the built-in advanced parser
+pub const ADVANCED: &str = "advanced";
+
+/// Name of the built-in simple parser
+pub const SIMPLE: &str = "simple";
+
+/// Name of the built-in hybrid scanner
+pub const HYBRID: &str = "hybrid";
+
+/// Name of the built-in rapified config reader
+pub const RAPIFIED: &str = "rapified";
+
+/// What a parser can extract from a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParserCapabilities {
+    /// Reads plain string and number property values
+    pub properties: bool,
+    /// Also reads array and expression values
+    pub arrays: bool,
+    /// Reports source spans for classes and properties
+    pub spans: bool,
+    /// Runs the preprocessor, so macros and includes are expanded
+    pub macros: bool,
+}
+
+impl ParserCapabilities {
+    /// Every capability
+    pub const FULL: Self = Self {
+        properties: true,
+        arrays: true,
+        spans: true,
+        macros: true,
+    };
+}
+
+/// A registered parser
+#[derive(Clone)]
+pub struct ParserEntry {
+    /// Name the parser is registered under
+    pub name: String,
+    /// What the parser can extract
+    pub capabilities: ParserCapabilities,
+    /// Scanner implementation, if registered
+    pub scanner: Option<Arc<dyn ClassScanner>>,
+    /// File parser implementation, if registered
+    pub file_parser: Option<Arc<dyn FileParser>>,
+    /// Registered by [`ParserRegistry::default`] and not replaced since
+    builtin: bool,
+}
+
+impl ParserEntry {
+    /// Whether this is a built-in parser rather than one registered by the caller
+    ///
+    /// The scanner calls the built-in `advanced` parser directly, to get
+    /// preprocessor info, diagnostics and partial parses; a parser registered
+    /// under that name is used like any other.
+    pub fn is_builtin(&self) -> bool {
+        self.builtin
+    }
+}
+
+impl fmt::Debug for ParserEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParserEntry")
+            .field("name", &self.name)
+            .field("capabilities", &self.capabilities)
+            .field("scanner", &self.scanner.is_some())
+            .field("file_parser", &self.file_parser.is_some())
+            .field("builtin", &self.builtin)
+            .finish()
+    }
+}
+
+/// Parsers available by name
+///
+/// [`ParserRegistry::default`] contains the built-in `advanced`, `simple`,
+/// `hybrid` and `rapified` parsers; [`ParserRegistry::new`] starts empty.
+/// Names are case-insensitive.
+#[derive(Debug, Clone)]
+pub struct ParserRegistry {
+    entries: BTreeMap<String, ParserEntry>,
+}
+
+impl ParserRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Register a scanner under `name`, replacing any scanner with that name
+    ///
+    /// The capabilities replace those of an existing entry with the same name.
+    pub fn register_scanner(&mut self, name: &str, capabilities: ParserCapabilities, scanner: Arc<dyn ClassScanner>) -> &mut Self {
+        let entry = self.entry(name, capabilities);
+        entry.scanner = Some(scanner);
+        self
+    }
+
+    /// Register a file parser under `name`, replacing any file parser with that name
+    ///
+    /// The capabilities replace those of an existing entry with the same name.
+    pub fn register_file_parser(&mut self, name: &str, capabilities: ParserCapabilities, parser: Arc<dyn FileParser>) -> &mut Self {
+        let entry = self.entry(name, capabilities);
+        entry.file_parser = Some(parser);
+        self
+    }
+
+    /// Look up a parser by name
+    pub fn get(&self, name: &str) -> Option<&ParserEntry> {
+        self.entries.get(&name.to_lowercase())
+    }
+
+    /// Scanner registered under `name`
+    pub fn scanner(&self, name: &str) -> Option<Arc<dyn ClassScanner>> {
+        self.get(name).and_then(|entry| entry.scanner.clone())
+    }
+
+    /// File parser registered under `name`
+    pub fn file_parser(&self, name: &str) -> Option<Arc<dyn FileParser>> {
+        self.get(name).and_then(|entry| entry.file_parser.clone())
+    }
+
+    /// Capabilities of the parser registered under `name`
+    pub fn capabilities(&self, name: &str) -> Option<ParserCapabilities> {
+        self.get(name).map(|entry| entry.capabilities)
+    }
+
+    /// Names of all registered parsers, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.values().map(|entry| entry.name.as_str())
+    }
+
+    fn entry(&mut self, name: &str, capabilities: ParserCapabilities) -> &mut ParserEntry {
+        let entry = self.entries.entry(name.to_lowercase()).or_insert_with(|| ParserEntry {
+            name: name.to_string(),
+            capabilities,
+            scanner: None,
+            file_parser: None,
+            builtin: false,
+        });
+        entry.capabilities = capabilities;
+        entry.builtin = false;
+        entry
+    }
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register_scanner(ADVANCED, ParserCapabilities::FULL, Arc::new(parser_advanced::scanner::AdvancedScanner::new()))
+            .register_file_parser(ADVANCED, ParserCapabilities::FULL, Arc::new(parser_advanced::AdvancedFileParser::new()));
+
+        let simple = ParserCapabilities {
+            properties: true,
+            arrays: false,
+            spans: true,
+            macros: false,
+        };
+        registry
+            .register_scanner(SIMPLE, simple, Arc::new(parser_simple::scanner::SimpleScanner::new()))
+            .register_file_parser(SIMPLE, simple, Arc::new(parser_simple::SimpleFileParser::new()));
+
+        // Only what the simple fallback also provides is guaranteed for every file
+        registry.register_scanner(HYBRID, simple, Arc::new(HybridScanner::new()));
+
+        // Binarized configs were preprocessed when they were built
+        let rapified = ParserCapabilities {
+            properties: true,
+            arrays: true,
+            spans: false,
+            macros: true,
+        };
+        registry
+            .register_scanner(RAPIFIED, rapified, Arc::new(parser_rapified::scanner::RapifiedScanner::new()))
+            .register_file_parser(RAPIFIED, rapified, Arc::new(parser_rapified::RapifiedFileParser::new()));
+
+        for entry in registry.entries.values_mut() {
+            entry.builtin = true;
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use gamedata_scanner_models::{GameClass, ScanError, ScanResult};
+
+    struct NamesOnly;
+
+    impl ClassScanner for NamesOnly {
+        fn scan_directory(&self, _dir_path: &Path) -> Result<ScanResult, String> {
+            Ok(ScanResult::new())
+        }
+
+        fn parse_file(&self, file_path: &Path) -> Result<Vec<GameClass>, Vec<ScanError>> {
+            Ok(vec![GameClass::new("InHouse".to_string(), None, file_path.to_path_buf())])
+        }
+    }
+
+    #[test]
+    fn test_builtin_parsers() {
+        let registry = ParserRegistry::default();
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["advanced", "hybrid", "rapified", "simple"]);
+
+        assert!(registry.file_parser("Advanced").is_some());
+        assert!(registry.file_parser(HYBRID).is_none());
+        assert!(registry.get(ADVANCED).unwrap().is_builtin());
+        assert!(registry.scanner("unknown").is_none());
+
+        let simple = registry.capabilities(SIMPLE).unwrap();
+        assert!(simple.properties && !simple.arrays && !simple.macros);
+        assert_eq!(registry.capabilities(HYBRID), Some(simple));
+    }
+
+    #[test]
+    fn test_register_custom_scanner() {
+        let mut registry = ParserRegistry::default();
+        let capabilities = ParserCapabilities { spans: false, ..ParserCapabilities::FULL };
+        registry.register_scanner("InHouse", capabilities, Arc::new(NamesOnly));
+
+        let entry = registry.get("inhouse").unwrap();
+        assert_eq!(entry.name, "InHouse");
+        assert_eq!(entry.capabilities, capabilities);
+        assert!(!entry.is_builtin());
+
+        // Replacing a built-in parser makes it an ordinary entry
+        registry.register_scanner(ADVANCED, capabilities, Arc::new(NamesOnly));
+        assert!(!registry.get(ADVANCED).unwrap().is_builtin());
+
+        let classes = registry.scanner("inhouse").unwrap().parse_file(Path::new("config.cpp")).unwrap();
+        assert_eq!(classes[0].name, "InHouse");
+    }
+}
@@ -0,0 +1,226 @@
+//! PBO archives found while scanning
+//!
+//! Config entries of every archive are extracted to a temporary directory so
+//! the parsers (and their include resolution) can read them like loose files.
+//! Results are then reported under the archive path, e.g.
+//! `addons/foo.pbo!/config.cpp`, so the temporary directory never shows up.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use log::{debug, warn};
+use pbo_reader::PboArchive;
+use tempfile::TempDir;
+use gamedata_scanner_models::{ClassProperty, GameClass, PropertyValue, ScanError, SourceSpan};
+
+use crate::scanner::{is_target_file, FileScanResult};
+use crate::virtual_paths::AddonInfo;
+
+/// Extensions of entries extracted only so includes resolve
+const INCLUDE_EXTENSIONS: &[&str] = &["h", "hh", "hpp", "inc", "ext"];
+
+/// Root of the entries of a PBO in scan results, e.g. `addons/foo.pbo!`
+pub fn archive_root(pbo_path: &Path) -> PathBuf {
+    let mut root = pbo_path.as_os_str().to_owned();
+    root.push("!");
+    PathBuf::from(root)
+}
+
+/// Path of a PBO entry in scan results, e.g. `addons/foo.pbo!/config.cpp`
+pub fn archive_entry_path(pbo_path: &Path, entry_name: &str) -> PathBuf {
+    entry_name
+        .split(['\\', '/'])
+        .filter(|part| !part.is_empty())
+        .fold(archive_root(pbo_path), |path, part| path.join(part))
+}
+
+/// An archive extracted for the duration of a scan
+#[derive(Debug)]
+struct Extracted {
+    /// Directory the entries were extracted to
+    dir: PathBuf,
+    /// Root of the entries in scan results
+    root: PathBuf,
+    /// Mount prefix, from the header or the archive name
+    prefix: String,
+    /// Whether the prefix came from the header
+    has_prefix: bool,
+}
+
+/// Config entries extracted from the PBOs of a scan
+#[derive(Debug)]
+pub(crate) struct ExtractedArchives {
+    _temp_dir: Option<TempDir>,
+    archives: Vec<Extracted>,
+    /// Extracted files to parse
+    pub files: Vec<PathBuf>,
+    /// Archives that could not be read
+    pub failures: Vec<(PathBuf, Vec<ScanError>)>,
+}
+
+impl ExtractedArchives {
+    /// Extract the entries of `pbo_paths` that have one of `extensions`, or are includes
+    pub fn extract(pbo_paths: &[PathBuf], extensions: &[String]) -> io::Result<Self> {
+        let mut extracted = Self {
+            _temp_dir: None,
+            archives: Vec::new(),
+            files: Vec::new(),
+            failures: Vec::new(),
+        };
+        if pbo_paths.is_empty() {
+            return Ok(extracted);
+        }
+
+        let temp_dir = TempDir::new()?;
+        for (index, pbo_path) in pbo_paths.iter().enumerate() {
+            let dir = temp_dir.path().join(index.to_string());
+            let wanted = |entry: &pbo_reader::PboEntry| {
+                entry.extension().is_some_and(|ext| {
+                    extensions.contains(&ext) || INCLUDE_EXTENSIONS.contains(&ext.as_str())
+                })
+            };
+
+            let opened = PboArchive::open(pbo_path).and_then(|pbo| {
+                let entries = pbo.extract(&dir, wanted)?;
+                Ok((pbo, entries))
+            });
+            let (pbo, entries) = match opened {
+                Ok(opened) => opened,
+                Err(e) => {
+                    warn!("Cannot read PBO {}: {}", pbo_path.display(), e);
+                    extracted.failures.push((pbo_path.clone(), vec![ScanError::io(pbo_path, &e)]));
+                    continue;
+                }
+            };
+
+            debug!("Extracted {} entries from {}", entries.len(), pbo_path.display());
+            extracted.files.extend(
+                entries.into_iter()
+                    .map(|(_, path)| path)
+                    .filter(|path| is_target_file(path, extensions)),
+            );
+
+            let header_prefix = pbo.prefix().map(|prefix| prefix.replace('/', "\\"));
+            let has_prefix = header_prefix.is_some();
+            let prefix = header_prefix.unwrap_or_else(|| {
+                pbo_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+            });
+            extracted.archives.push(Extracted {
+                dir,
+                root: archive_root(pbo_path),
+                prefix,
+                has_prefix,
+            });
+        }
+
+        extracted._temp_dir = Some(temp_dir);
+        Ok(extracted)
+    }
+
+    /// Addons backed by the extracted directories, for include resolution
+    pub fn extracted_addons(&self) -> impl Iterator<Item = AddonInfo> + '_ {
+        self.archives.iter().map(|archive| AddonInfo {
+            prefix: archive.prefix.clone(),
+            path: archive.dir.clone(),
+            has_prefix_file: archive.has_prefix,
+        })
+    }
+
+    /// Addons rooted at the archive paths, as reported in scan results
+    pub fn archive_addons(&self) -> impl Iterator<Item = AddonInfo> + '_ {
+        self.archives.iter().map(|archive| AddonInfo {
+            prefix: archive.prefix.clone(),
+            path: archive.root.clone(),
+            has_prefix_file: archive.has_prefix,
+        })
+    }
+
+    /// Directory the archive containing the extracted file `path` was extracted to
+    pub fn extracted_dir(&self, path: &Path) -> Option<&Path> {
+        self.archives.iter()
+            .find(|archive| path.starts_with(&archive.dir))
+            .map(|archive| archive.dir.as_path())
+    }
+
+    /// Path of `path` in scan results: extracted files map to their archive entry
+    pub fn relocate(&self, path: &Path) -> PathBuf {
+        self.archives.iter()
+            .find_map(|archive| {
+                let relative = path.strip_prefix(&archive.dir).ok()?;
+                Some(archive.root.join(relative))
+            })
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Rewrite every path of a scan result that points into an extracted archive
+    pub fn relocate_result(&self, result: &mut FileScanResult) {
+        if self.archives.is_empty() {
+            return;
+        }
+
+        result.file_path = self.relocate(&result.file_path);
+        for class in &mut result.classes {
+            self.relocate_class(class);
+        }
+        for diagnostic in &mut result.diagnostics {
+            diagnostic.file = self.relocate(&diagnostic.file);
+            self.relocate_span(&mut diagnostic.span);
+        }
+        self.relocate_errors(&mut result.errors);
+
+        if let Some(preprocessor) = &mut result.preprocessor {
+            for define in &mut preprocessor.defines {
+                define.file = self.relocate(&define.file);
+            }
+            for expansion in &mut preprocessor.expansions {
+                expansion.file = self.relocate(&expansion.file);
+            }
+            for include in &mut preprocessor.includes {
+                include.file = self.relocate(&include.file);
+                include.resolved = include.resolved.as_deref().map(|path| self.relocate(path));
+            }
+        }
+    }
+
+    /// Rewrite the paths of errors that point into an extracted archive
+    pub fn relocate_errors(&self, errors: &mut [ScanError]) {
+        for error in errors {
+            let diagnostic = error.diagnostic_mut();
+            diagnostic.file = self.relocate(&diagnostic.file);
+            self.relocate_span(&mut diagnostic.span);
+        }
+    }
+
+    fn relocate_class(&self, class: &mut GameClass) {
+        class.file_path = self.relocate(&class.file_path);
+        self.relocate_span(&mut class.span);
+        for property in &mut class.properties {
+            self.relocate_property(property);
+        }
+    }
+
+    fn relocate_property(&self, property: &mut ClassProperty) {
+        self.relocate_span(&mut property.span);
+        if let PropertyValue::Class(nested) = &mut property.value {
+            self.relocate_class(nested);
+        }
+    }
+
+    fn relocate_span(&self, span: &mut Option<SourceSpan>) {
+        if let Some(span) = span {
+            span.file = self.relocate(&span.file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_entry_path() {
+        let pbo = Path::new("addons").join("foo.pbo");
+        assert_eq!(archive_entry_path(&pbo, "data\\config.cpp"), Path::new("addons").join("foo.pbo!").join("data").join("config.cpp"));
+        assert!(archive_entry_path(&pbo, "config.cpp").starts_with(archive_root(&pbo)));
+        assert!(!Path::new("addons/foo.pbo/config.cpp").starts_with(archive_root(&pbo)));
+    }
+}
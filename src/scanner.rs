@@ -6,11 +6,15 @@ use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use rayon::prelude::*;
 use indicatif::{ProgressBar, ProgressStyle};
-use parser_advanced::parse_file;
-use gamedata_scanner_models::GameClass;
+use parser_advanced::{parse_file_detailed, parse_file_recovering, ParseOptions, VirtualIncludeRoot};
+use gamedata_scanner_models::{ClassKind, ConfigTree, Diagnostic, GameClass, LoadOrder, ParsedFile, ParseStatus, PreprocessorInfo, ScanError, Scanner as ClassScanner, Severity};
 use serde::{Deserialize, Serialize};
 use std::thread;
 use std::sync::mpsc::{self, RecvTimeoutError};
+use crate::mods::{ModInfo, ModMap};
+use crate::parser_registry::{ParserRegistry, ADVANCED};
+use crate::pbo_archives::ExtractedArchives;
+use crate::virtual_paths::VirtualPathMap;
 
 /// Result of scanning a single file, containing the parsed classes
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +25,24 @@ pub struct FileScanResult {
     pub classes: Vec<GameClass>,
     /// Time taken to parse the file in milliseconds (only set if diagnostic mode is enabled)
     pub parse_time_ms: Option<u64>,
+    /// Prefix of the addon the file belongs to (e.g. `z\ace\addons\medical`)
+    #[serde(default)]
+    pub addon: Option<String>,
+    /// In-game path of the file (e.g. `z\ace\addons\medical\config.cpp`)
+    #[serde(default)]
+    pub virtual_path: Option<String>,
+    /// Macro definitions, macro uses and includes seen while preprocessing the file
+    #[serde(default)]
+    pub preprocessor: Option<PreprocessorInfo>,
+    /// Warnings and other non-fatal issues, e.g. unused macros
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether all classes of the file were read
+    #[serde(default)]
+    pub status: ParseStatus,
+    /// Errors of the classes that were skipped, for partial parses
+    #[serde(default)]
+    pub errors: Vec<ScanError>,
 }
 
 /// Configuration for the scanner
@@ -30,12 +52,35 @@ pub struct ScannerConfig {
     pub max_files: Option<usize>,
     /// Whether to show progress bar
     pub show_progress: bool,
-    /// File extensions to scan (lowercase)
+    /// File extensions to scan (lowercase); `bin` files are only scanned if they are rapified configs
     pub extensions: Vec<String>,
     /// Timeout in seconds for processing each file (default: 30)
     pub timeout: u64,
     /// Enable diagnostic mode to track parsing time for each file
     pub diagnostic_mode: bool,
+    /// Keep the classes that parse when a file has syntax errors (see [`ParseStatus::Partial`])
+    ///
+    /// Off by default: recovery preprocesses a broken file again for every
+    /// attempt (see [`parse_file_recovering`]), which adds up on large trees.
+    pub recover_partial: bool,
+    /// Extra P-drive style directories searched for `#include "\x\..."` directives
+    pub include_roots: Vec<PathBuf>,
+    /// Map of in-game path prefixes (the `$PBOPREFIX$` convention, e.g.
+    /// `z\ace\addons\main`) to the directories on disk that provide them
+    pub prefix_mappings: HashMap<String, PathBuf>,
+    /// Name of the parser in `registry` to use (default: `advanced`)
+    ///
+    /// Only the built-in `advanced` parser records preprocessor info,
+    /// diagnostics and partial parses; other parsers only report classes.
+    pub parser: String,
+    /// Parsers available to `parser`
+    pub registry: ParserRegistry,
+    /// Descend into `.pbo` archives, reporting their entries as
+    /// `<archive>!/<entry>` (e.g. `addons/foo.pbo!/config.cpp`)
+    pub scan_archives: bool,
+    /// Recognise mod folders (`@Mod` with an `addons` folder, `mod.cpp`,
+    /// `meta.cpp`) so results can be grouped by mod
+    pub mod_aware: bool,
 }
 
 impl Default for ScannerConfig {
@@ -43,9 +88,16 @@ impl Default for ScannerConfig {
         Self {
             max_files: None,
             show_progress: true,
-            extensions: vec!["hpp".to_string(), "cpp".to_string(), "ext".to_string()],
+            extensions: TEXT_EXTENSIONS.iter().chain(&["bin"]).map(|ext| ext.to_string()).collect(),
             timeout: 120,
             diagnostic_mode: false,
+            recover_partial: false,
+            include_roots: Vec::new(),
+            prefix_mappings: HashMap::new(),
+            parser: ADVANCED.to_string(),
+            registry: ParserRegistry::default(),
+            scan_archives: false,
+            mod_aware: false,
         }
     }
 }
@@ -61,10 +113,101 @@ pub struct ScannerResult {
     pub failed_files: usize,
     /// Map of file paths to their scan results
     pub results: HashMap<PathBuf, FileScanResult>,
-    /// Map of file paths to their error messages
-    pub errors: HashMap<PathBuf, String>,
+    /// Map of file paths to the errors that made them fail
+    pub errors: HashMap<PathBuf, Vec<ScanError>>,
     /// Diagnostic information (only available if diagnostic mode was enabled)
     pub diagnostics: Option<DiagnosticInfo>,
+    /// Addons found in the scanned tree and their in-game paths
+    pub addons: VirtualPathMap,
+    /// Mods found in the scanned tree (only in mod-aware mode)
+    pub mods: ModMap,
+}
+
+impl ScannerResult {
+    /// Scan results grouped by addon prefix; files outside any addon are left out
+    pub fn results_by_addon(&self) -> HashMap<&str, Vec<&FileScanResult>> {
+        let mut grouped: HashMap<&str, Vec<&FileScanResult>> = HashMap::new();
+        for result in self.results.values() {
+            if let Some(addon) = &result.addon {
+                grouped.entry(addon.as_str()).or_default().push(result);
+            }
+        }
+        grouped
+    }
+    
+    /// Scan results grouped by mod, in the order the mods were found; files outside any mod are left out
+    pub fn results_by_mod(&self) -> Vec<(&ModInfo, Vec<&FileScanResult>)> {
+        let mut grouped: Vec<(&ModInfo, Vec<&FileScanResult>)> = self.mods.mods().iter()
+            .map(|info| (info, Vec::new()))
+            .collect();
+        for result in self.results.values() {
+            if let Some(info) = self.mods.mod_for(&result.file_path) {
+                if let Some((_, results)) = grouped.iter_mut().find(|(known, _)| known.path == info.path) {
+                    results.push(result);
+                }
+            }
+        }
+        grouped
+    }
+    
+    /// Mods with a file defining a class named `class_name` (case-insensitive), ordered by path
+    pub fn mods_defining(&self, class_name: &str) -> Vec<&ModInfo> {
+        let mut found: Vec<&ModInfo> = Vec::new();
+        for class in self.sorted_classes() {
+            if class.kind != ClassKind::Definition || !class.name.eq_ignore_ascii_case(class_name) {
+                continue;
+            }
+            if let Some(info) = self.mods.mod_for(&class.file_path) {
+                if !found.iter().any(|known| known.path == info.path) {
+                    found.push(info);
+                }
+            }
+        }
+        found
+    }
+    
+    /// Addon load order from the `CfgPatches` classes of all scanned files
+    pub fn load_order(&self) -> LoadOrder {
+        LoadOrder::from_classes(self.sorted_classes())
+    }
+    
+    /// Merge the classes of all scanned files into one [`ConfigTree`]
+    ///
+    /// Files are merged in addon load order (see [`ScannerResult::load_order`]),
+    /// falling back to path order, so the result does not depend on scan order.
+    pub fn config_tree(&self) -> ConfigTree {
+        let mut classes = self.sorted_classes();
+        self.load_order().sort_classes(&mut classes);
+        ConfigTree::from_classes(classes)
+    }
+    
+    /// All classes ordered by file path
+    fn sorted_classes(&self) -> Vec<&GameClass> {
+        let mut files: Vec<&FileScanResult> = self.results.values().collect();
+        files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        files.into_iter().flat_map(|result| &result.classes).collect()
+    }
+    
+    /// Scan results of files that parsed but reported warnings
+    pub fn files_with_warnings(&self) -> impl Iterator<Item = &FileScanResult> {
+        self.results.values().filter(|result| {
+            result.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Warning)
+        })
+    }
+    
+    /// Scan results of files where classes with syntax errors were skipped
+    pub fn partial_results(&self) -> impl Iterator<Item = &FileScanResult> {
+        self.results.values().filter(|result| result.status == ParseStatus::Partial)
+    }
+    
+    /// Find the scan result for an in-game path (case-insensitive)
+    pub fn result_for_virtual_path(&self, virtual_path: &str) -> Option<&FileScanResult> {
+        let wanted = virtual_path.trim_start_matches('\\');
+        self.results.values().find(|result| {
+            result.virtual_path.as_deref()
+                .is_some_and(|path| path.eq_ignore_ascii_case(wanted))
+        })
+    }
 }
 
 /// Diagnostic information about the scanning process
@@ -84,7 +227,7 @@ impl DiagnosticInfo {
     /// Create a new diagnostic info from scan results
     fn new(
         results: &HashMap<PathBuf, FileScanResult>,
-        errors: &HashMap<PathBuf, String>,
+        errors: &HashMap<PathBuf, Vec<ScanError>>,
         total_scan_time_ms: u64
     ) -> Self {
         // Extract parse times from results
@@ -97,7 +240,7 @@ impl DiagnosticInfo {
         
         // Count timeout errors
         let timeout_count = errors.values()
-            .filter(|e| e.contains("timed out"))
+            .filter(|e| e.iter().any(ScanError::is_timeout))
             .count();
         
         // Create sorted list of slowest files
@@ -153,6 +296,38 @@ impl DiagnosticInfo {
     }
 }
 
+/// Options resolving includes the way [`scan_directory`] does for the files under `root_dir`
+///
+/// Prefix mappings, both configured and those of the `addons` found in the
+/// tree, are exposed as a P-drive style include root. The returned
+/// [`VirtualIncludeRoot`] backs that include root and has to be kept alive
+/// while the options are in use.
+pub fn include_options(
+    root_dir: &Path,
+    config: &ScannerConfig,
+    addons: &VirtualPathMap,
+) -> io::Result<(ParseOptions, Option<VirtualIncludeRoot>)> {
+    // Explicit mappings come first so they win over discovered addons with the same prefix
+    let virtual_root = if config.prefix_mappings.is_empty() && addons.prefix_mappings().next().is_none() {
+        None
+    } else {
+        let configured = config.prefix_mappings.iter()
+            .map(|(prefix, dir)| (prefix.as_str(), dir.as_path()));
+        let discovered = addons.prefix_mappings()
+            .filter(|(prefix, _)| !config.prefix_mappings.keys().any(|p| p.eq_ignore_ascii_case(prefix)));
+        Some(VirtualIncludeRoot::new(configured.chain(discovered))?)
+    };
+    
+    // Addon roots are searched for inside the scanned tree only
+    let mut options = ParseOptions::new()
+        .with_scan_root(std::path::absolute(root_dir)?);
+    options.include_roots = config.include_roots.clone();
+    if let Some(root) = &virtual_root {
+        options.include_roots.push(root.path().to_path_buf());
+    }
+    Ok((options, virtual_root))
+}
+
 /// Scans a directory recursively for game data files and processes them in parallel
 pub fn scan_directory(
     root_dir: impl AsRef<Path>,
@@ -171,20 +346,55 @@ pub fn scan_directory(
         ));
     }
 
-    // First, collect all files to process
+    // Parsers are used through the registry, except the built-in advanced one,
+    // which is called directly for the details
+    let entry = config.registry.get(&config.parser).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Unknown parser: {}", config.parser),
+    ))?;
+    let scanner = if entry.is_builtin() && entry.name.eq_ignore_ascii_case(ADVANCED) {
+        None
+    } else {
+        let scanner = entry.scanner.clone().ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Parser {} has no scanner", entry.name),
+        ))?;
+        Some(scanner)
+    };
+
+    // First, collect all files to process, noting addon roots and archives along the way
     let mut files = Vec::new();
-    collect_files_recursive(root_dir, &mut files, &config)?;
+    let mut archive_paths = Vec::new();
+    let mut addons = VirtualPathMap::new();
+    let mut mods = ModMap::new();
+    collect_files_recursive(root_dir, &mut files, &mut archive_paths, &mut addons, &mut mods, &config)?;
+
+    // Archive entries are parsed from a temporary copy, but includes resolve
+    // through the extracted directories and results use the archive paths
+    let archives = ExtractedArchives::extract(&archive_paths, &config.extensions)?;
+    files.extend(archives.files.iter().cloned());
+    let mut include_addons = addons.clone();
+    for addon in archives.extracted_addons() {
+        include_addons.insert(addon);
+    }
+    for addon in archives.archive_addons() {
+        addons.insert(addon);
+    }
+    mods.assign_addons(&addons);
 
     // Apply max_files limit if specified
     if let Some(max) = config.max_files {
         files.truncate(max);
     }
+    
+    // The virtual include root has to outlive the scan
+    let (parse_options, _virtual_root) = include_options(root_dir, &config, &include_addons)?;
 
     // Create shared result containers
     let results = Arc::new(Mutex::new(HashMap::new()));
-    let errors = Arc::new(Mutex::new(HashMap::new()));
+    let errors = Arc::new(Mutex::new(archives.failures.iter().cloned().collect::<HashMap<_, _>>()));
     let successful_count = Arc::new(Mutex::new(0usize));
-    let failed_count = Arc::new(Mutex::new(0usize));
+    let failed_count = Arc::new(Mutex::new(archives.failures.len()));
 
     // Create progress bar if enabled
     let progress_bar = if config.show_progress {
@@ -200,13 +410,22 @@ pub fn scan_directory(
 
     // Process files in parallel
     files.par_iter().for_each(|file_path| {
-        match process_file(file_path, config.timeout, config.diagnostic_mode) {
-            Ok(scan_result) => {
-                results.lock().unwrap().insert(file_path.clone(), scan_result);
+        // Extracted archive entries live outside the scanned tree, their search stops at the archive
+        let archive_options = archives.extracted_dir(file_path)
+            .map(|dir| parse_options.clone().with_scan_root(dir));
+        let parse_options = archive_options.as_ref().unwrap_or(&parse_options);
+        match process_file(file_path, parse_options, scanner.clone(), config.timeout, config.diagnostic_mode, config.recover_partial) {
+            Ok(mut scan_result) => {
+                archives.relocate_result(&mut scan_result);
+                let path = scan_result.file_path.clone();
+                scan_result.addon = addons.addon_for(&path).map(|addon| addon.prefix.clone());
+                scan_result.virtual_path = addons.virtual_path(&path);
+                results.lock().unwrap().insert(path, scan_result);
                 *successful_count.lock().unwrap() += 1;
             }
-            Err(e) => {
-                errors.lock().unwrap().insert(file_path.clone(), e.to_string());
+            Err(mut e) => {
+                archives.relocate_errors(&mut e);
+                errors.lock().unwrap().insert(archives.relocate(file_path), e);
                 *failed_count.lock().unwrap() += 1;
             }
         }
@@ -243,32 +462,44 @@ pub fn scan_directory(
     let failed = *failed_count.lock().unwrap();
     
     Ok(ScannerResult {
-        total_files: files.len(),
+        total_files: files.len() + archives.failures.len(),
         successful_files: successful,
         failed_files: failed,
         results: results_map,
         errors: errors_map,
         diagnostics,
+        addons,
+        mods,
     })
 }
 
-/// Recursively collects files to process
+/// Recursively collects files to process and, if enabled, PBO archives to descend into
 fn collect_files_recursive(
     dir: &Path,
     files: &mut Vec<PathBuf>,
+    archives: &mut Vec<PathBuf>,
+    addons: &mut VirtualPathMap,
+    mods: &mut ModMap,
     config: &ScannerConfig,
 ) -> io::Result<()> {
     if !dir.is_dir() {
         return Ok(());
     }
+    
+    addons.inspect_dir(dir);
+    if config.mod_aware {
+        mods.inspect_dir(dir);
+    }
 
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
 
         if path.is_dir() {
-            collect_files_recursive(&path, files, config)?;
-        } else if is_target_file(&path, &config.extensions) {
+            collect_files_recursive(&path, files, archives, addons, mods, config)?;
+        } else if config.scan_archives && pbo_reader::is_pbo_file(&path) {
+            archives.push(path);
+        } else if is_target_file(&path, &config.extensions) && !mods.is_metadata_file(&path) {
             files.push(path);
         }
     }
@@ -276,42 +507,80 @@ fn collect_files_recursive(
     Ok(())
 }
 
+/// Extensions of the text configs scanned by default, rapified `.bin` configs aside
+pub(crate) const TEXT_EXTENSIONS: &[&str] = &["hpp", "cpp", "ext"];
+
 /// Checks if a file should be processed based on its extension
-fn is_target_file(path: &Path, extensions: &[String]) -> bool {
+///
+/// `.bin` files also need the rapified header, so `texHeaders.bin` and the like are skipped.
+pub(crate) fn is_target_file(path: &Path, extensions: &[String]) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
         .map(|ext| extensions.contains(&ext.to_lowercase()))
         .unwrap_or(false)
+        && (!is_bin_file(path) || parser_rapified::is_rapified_file(path))
+}
+
+/// Checks if a file has the `.bin` extension of rapified configs
+fn is_bin_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bin"))
 }
 
 /// Process a single file
-fn process_file(file_path: &Path, timeout_secs: u64, diagnostic_mode: bool) -> io::Result<FileScanResult> {
+fn process_file(
+    file_path: &Path,
+    options: &ParseOptions,
+    scanner: Option<Arc<dyn ClassScanner>>,
+    timeout_secs: u64,
+    diagnostic_mode: bool,
+    recover_partial: bool,
+) -> Result<FileScanResult, Vec<ScanError>> {
     // Create a channel for the worker thread to send results back
     let (tx, rx) = mpsc::channel();
     
-    // Clone the path for the worker thread
+    // Clone the path and options for the worker thread
     let file_path_clone = file_path.to_path_buf();
+    let options = options.clone();
     
     // Spawn a worker thread to process the file
     thread::spawn(move || {
         // Start timing if diagnostic mode is enabled
         let start_time = if diagnostic_mode { Some(Instant::now()) } else { None };
         
-        let result = match parse_file(&file_path_clone) {
-            Ok(classes) => {
+        // Rapified configs are decoded whichever parser is selected
+        let rapified = is_bin_file(&file_path_clone);
+        let classes_only = |classes| ParsedFile {
+            classes,
+            preprocessor: PreprocessorInfo::default(),
+            diagnostics: Vec::new(),
+            status: ParseStatus::Complete,
+            errors: Vec::new(),
+        };
+        let parsed = match &scanner {
+            _ if rapified => parser_rapified::parse_file_detailed(&file_path_clone),
+            Some(scanner) => scanner.parse_file(&file_path_clone).map(classes_only),
+            None if recover_partial => parse_file_recovering(&file_path_clone, &options),
+            None => parse_file_detailed(&file_path_clone, &options),
+        };
+        
+        let result = match parsed {
+            Ok(parsed) => {
                 // Calculate parsing time if diagnostic mode is enabled
                 let parse_time_ms = start_time.map(|start| start.elapsed().as_millis() as u64);
                 
                 Ok(FileScanResult {
                     file_path: file_path_clone.clone(),
-                    classes,
+                    classes: parsed.classes,
                     parse_time_ms,
+                    addon: None,
+                    virtual_path: None,
+                    preprocessor: (scanner.is_none() && !rapified).then_some(parsed.preprocessor),
+                    diagnostics: parsed.diagnostics,
+                    status: parsed.status,
+                    errors: parsed.errors,
                 })
             },
-            Err(errors) => Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Parse errors: {:?}", errors),
-            )),
+            Err(errors) => Err(errors),
         };
         
         // Send the result back to the main thread
@@ -322,18 +591,11 @@ fn process_file(file_path: &Path, timeout_secs: u64, diagnostic_mode: bool) -> i
     match rx.recv_timeout(Duration::from_secs(timeout_secs)) {
         Ok(result) => result,
         Err(RecvTimeoutError::Timeout) => {
-            Err(io::Error::new(
-                io::ErrorKind::TimedOut,
-                format!("Processing timed out after {} seconds for file: {}", 
-                        timeout_secs, file_path.display())
-            ))
+            Err(vec![ScanError::timeout(file_path, timeout_secs)])
         },
         Err(RecvTimeoutError::Disconnected) => {
-            Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Worker thread disconnected while processing file: {}", 
-                        file_path.display())
-            ))
+            // The worker only drops the sender without sending if the parser panicked
+            Err(vec![ScanError::panicked(file_path, "Worker thread disconnected while processing file")])
         }
     }
 }
@@ -344,6 +606,9 @@ mod tests {
     use tempfile::TempDir;
     use std::fs::File;
     use std::io::Write;
+    use gamedata_scanner_models::PropertyValue;
+    use pbo_reader::test_support::build_pbo;
+    use crate::parser_registry::ParserCapabilities;
 
     fn create_test_file(dir: &Path, name: &str, content: &str) -> io::Result<PathBuf> {
         let path = dir.join(name);
@@ -409,4 +674,657 @@ mod tests {
         
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_scanner_with_prefix_mappings() -> io::Result<()> {
+        let headers_dir = TempDir::new()?;
+        create_test_file(headers_dir.path(), "script_macros.hpp", r#"
+            #define GVAR(var1) ace_main_##var1
+        "#)?;
+        
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "config.cpp", r#"
+            #include "\z\ace\addons\main\script_macros.hpp"
+            class GVAR(settings) {
+                enabled = 1;
+            };
+        "#)?;
+        
+        let mut config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        config.prefix_mappings.insert(
+            "z\\ace\\addons\\main".to_string(),
+            headers_dir.path().to_path_buf(),
+        );
+        
+        let result = scan_directory(temp_dir.path(), config)?;
+        
+        assert_eq!(result.successful_files, 1);
+        let classes = &result.results.values().next().unwrap().classes;
+        assert!(classes.iter().any(|c| c.name == "ace_main_settings"));
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_options() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let main = temp_dir.path().join("main");
+        fs::create_dir_all(&main)?;
+        create_test_file(&main, "$PBOPREFIX$", "x\\cba\\addons\\main")?;
+        create_test_file(&main, "script_macros.hpp", "")?;
+        
+        let addons = VirtualPathMap::discover(temp_dir.path());
+        let (options, virtual_root) = include_options(temp_dir.path(), &ScannerConfig::default(), &addons)?;
+        assert!(virtual_root.is_some());
+        assert!(options.search_roots().iter().any(|root| root.join("x/cba/addons/main/script_macros.hpp").is_file()));
+        assert_eq!(options.scan_root, Some(std::path::absolute(temp_dir.path())?));
+        
+        // Nothing to map without prefixes
+        let (options, virtual_root) = include_options(temp_dir.path(), &ScannerConfig::default(), &VirtualPathMap::new())?;
+        assert!(virtual_root.is_none());
+        assert!(options.include_roots.is_empty());
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_reports_virtual_paths() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let addon_dir = temp_dir.path().join("addons").join("medical");
+        fs::create_dir_all(&addon_dir)?;
+        create_test_file(&addon_dir, "$PBOPREFIX$", "z\\ace\\addons\\medical")?;
+        create_test_file(&addon_dir, "script_component.hpp", "#define COMPONENT medical\n")?;
+        create_test_file(&addon_dir, "config.cpp", r#"
+            #include "\z\ace\addons\medical\script_component.hpp"
+            class CfgPatches {
+                class ace_medical {};
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        
+        let result = scan_directory(temp_dir.path(), config)?;
+        
+        assert_eq!(result.addons.addons().len(), 1);
+        assert_eq!(result.addons.addons()[0].prefix, "z\\ace\\addons\\medical");
+        
+        let config_result = result.result_for_virtual_path("\\z\\ace\\addons\\medical\\config.cpp")
+            .expect("config.cpp should be reachable by its in-game path");
+        assert_eq!(config_result.addon.as_deref(), Some("z\\ace\\addons\\medical"));
+        assert!(config_result.classes.iter().any(|c| c.name == "ace_medical"));
+        
+        assert_eq!(result.results_by_addon()["z\\ace\\addons\\medical"].len(), 2);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_tree_across_files() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "a_base.hpp", r#"
+            class CfgWeapons {
+                class Rifle_Base_F {
+                    class ItemInfo {
+                        mass = 10;
+                    };
+                };
+            };
+        "#)?;
+        create_test_file(temp_dir.path(), "b_mx.hpp", r#"
+            class CfgWeapons {
+                class Rifle_Base_F;
+                class arifle_MX_F: Rifle_Base_F {
+                    displayName = "MX";
+                    class ItemInfo: ItemInfo {
+                        mass = 4;
+                    };
+                };
+                class arifle_Broken_F: Missing_Base_F {};
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        let tree = scan_directory(temp_dir.path(), config)?.config_tree();
+        
+        let mx = tree.id_of("cfgweapons/ARIFLE_MX_F").expect("weapon should be in the tree");
+        assert_eq!(tree.parent_of(mx).unwrap().path, "CfgWeapons/Rifle_Base_F");
+        assert_eq!(tree.node(mx).sources.len(), 1);
+        
+        // ItemInfo: ItemInfo resolves through the weapon's own parent
+        let item_info = tree.id_of("CfgWeapons/arifle_MX_F/ItemInfo").unwrap();
+        assert_eq!(tree.parent_of(item_info).unwrap().path, "CfgWeapons/Rifle_Base_F/ItemInfo");
+        
+        // CfgWeapons is defined in both files and merged into one node
+        assert_eq!(tree.get("CfgWeapons").unwrap().sources.len(), 2);
+        
+        assert_eq!(tree.errors(), &[gamedata_scanner_models::ConfigTreeError::UnresolvedParent {
+            class_path: "CfgWeapons/arifle_Broken_F".to_string(),
+            parent: "Missing_Base_F".to_string(),
+        }]);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_effective_properties() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "config.hpp", r#"
+            class CfgWeapons {
+                class Rifle_Base_F {
+                    magazines[] = {"30Rnd_545x39_Mag"};
+                    reloadTime = 0.1;
+                    class ItemInfo {
+                        mass = 10;
+                        allowedSlots[] = {901};
+                    };
+                };
+                class rhs_weap_ak74m: Rifle_Base_F {
+                    reloadTime = 0.096;
+                    class ItemInfo: ItemInfo {
+                        mass = 76;
+                    };
+                };
+                class rhs_weap_ak74m_camo: rhs_weap_ak74m {};
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        let tree = scan_directory(temp_dir.path(), config)?.config_tree();
+        
+        let magazines = tree.effective_property("CfgWeapons/rhs_weap_ak74m_camo", "MAGAZINES").unwrap();
+        assert_eq!(magazines.source, "CfgWeapons/Rifle_Base_F");
+        assert_eq!(magazines.value.as_array().unwrap()[0].as_string(), Some("30Rnd_545x39_Mag"));
+        
+        let reload = tree.effective_property("CfgWeapons/rhs_weap_ak74m_camo", "reloadTime").unwrap();
+        assert_eq!(reload.source, "CfgWeapons/rhs_weap_ak74m");
+        assert!(!reload.is_local_to("CfgWeapons/rhs_weap_ak74m_camo"));
+        
+        // The camo variant has no ItemInfo of its own and inherits the AK's
+        let mass = tree.effective_property("CfgWeapons/rhs_weap_ak74m_camo/ItemInfo", "mass").unwrap();
+        assert_eq!(mass.value.as_number(), Some(76));
+        let slots = tree.effective_property("CfgWeapons/rhs_weap_ak74m/ItemInfo", "allowedSlots").unwrap();
+        assert_eq!(slots.source, "CfgWeapons/Rifle_Base_F/ItemInfo");
+        
+        let all = tree.effective_properties("CfgWeapons/rhs_weap_ak74m");
+        let names: Vec<&str> = all.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["magazines", "reloadTime"]);
+        assert!(tree.effective_property("CfgWeapons/rhs_weap_ak74m", "missing").is_none());
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_tree_follows_load_order() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        // "a_patch" sorts first by path but requires "z_base", so it must win
+        let patch_dir = temp_dir.path().join("a_patch");
+        let base_dir = temp_dir.path().join("z_base");
+        fs::create_dir_all(&patch_dir)?;
+        fs::create_dir_all(&base_dir)?;
+        create_test_file(&patch_dir, "config.cpp", r#"
+            class CfgPatches {
+                class my_patch {
+                    requiredAddons[] = {"my_base", "A3_Data_F"};
+                };
+            };
+            class CfgVehicles {
+                class Car {
+                    maxSpeed = 200;
+                };
+            };
+        "#)?;
+        create_test_file(&base_dir, "config.cpp", r#"
+            class CfgPatches {
+                class my_base {
+                    requiredAddons[] = {};
+                };
+            };
+            class CfgVehicles {
+                class Car {
+                    maxSpeed = 100;
+                    fuelCapacity = 50;
+                };
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config)?;
+        
+        let load_order = result.load_order();
+        let names: Vec<&str> = load_order.addons.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["my_base", "my_patch"]);
+        assert_eq!(load_order.errors, vec![gamedata_scanner_models::LoadOrderError::MissingDependency {
+            addon: "my_patch".to_string(),
+            dependency: "A3_Data_F".to_string(),
+        }]);
+        
+        let tree = result.config_tree();
+        let car = tree.get("CfgVehicles/Car").unwrap();
+        assert_eq!(car.property("maxSpeed").unwrap().value.as_number(), Some(200));
+        assert_eq!(car.property("fuelCapacity").unwrap().value.as_number(), Some(50));
+        assert_eq!(car.sources, vec![base_dir.join("config.cpp"), patch_dir.join("config.cpp")]);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_tree_applies_deletions() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "a_base.hpp", r#"
+            class CfgWeapons {
+                class Rifle_Base_F {};
+                class arifle_Old_F: Rifle_Base_F {
+                    class ItemInfo {};
+                };
+            };
+        "#)?;
+        create_test_file(temp_dir.path(), "b_cleanup.hpp", r#"
+            class CfgWeapons {
+                class Rifle_Base_F;
+                class Pistol_Base_F;
+                delete arifle_Old_F;
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        let tree = scan_directory(temp_dir.path(), config)?.config_tree();
+        
+        assert!(tree.get("CfgWeapons/arifle_Old_F").is_none());
+        assert!(tree.get("CfgWeapons/arifle_Old_F/ItemInfo").is_none());
+        
+        // Re-declaring an existing class doesn't touch its definition
+        let base = tree.get("CfgWeapons/Rifle_Base_F").unwrap();
+        assert_eq!(base.kind, gamedata_scanner_models::ClassKind::Definition);
+        assert_eq!(base.sources.len(), 1);
+        
+        // A class that is only ever declared stays external
+        let pistol = tree.get("CfgWeapons/Pistol_Base_F").unwrap();
+        assert_eq!(pistol.kind, gamedata_scanner_models::ClassKind::External);
+        
+        let names: Vec<&str> = tree.iter().map(|(_, node)| node.name.as_str()).collect();
+        assert_eq!(names, vec!["CfgWeapons", "Rifle_Base_F", "Pistol_Base_F"]);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_effective_array_append() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "a_base.hpp", r#"
+            class CfgWeapons {
+                class Rifle_Base_F {
+                    magazines[] = {"mag_a"};
+                };
+                class arifle_MX_F: Rifle_Base_F {
+                    magazines[] += {"mag_b"};
+                };
+                class arifle_MX_Black_F: arifle_MX_F {
+                    magazines[] += {"mag_c"};
+                };
+            };
+        "#)?;
+        create_test_file(temp_dir.path(), "b_patch.hpp", r#"
+            class CfgWeapons {
+                class arifle_MX_F {
+                    magazines[] += {"mag_d"};
+                };
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        let tree = scan_directory(temp_dir.path(), config)?.config_tree();
+        
+        let magazines = |path: &str| -> Vec<String> {
+            tree.effective_property(path, "magazines").unwrap()
+                .value.as_array().unwrap()
+                .iter()
+                .filter_map(|item| item.as_string().map(str::to_string))
+                .collect()
+        };
+        assert_eq!(magazines("CfgWeapons/Rifle_Base_F"), vec!["mag_a"]);
+        assert_eq!(magazines("CfgWeapons/arifle_MX_F"), vec!["mag_a", "mag_b", "mag_d"]);
+        assert_eq!(magazines("CfgWeapons/arifle_MX_Black_F"), vec!["mag_a", "mag_b", "mag_d", "mag_c"]);
+        
+        let all = tree.effective_properties("CfgWeapons/arifle_MX_Black_F");
+        assert_eq!(all[0].value.as_array().unwrap().len(), 4);
+        assert_eq!(all[0].source, "CfgWeapons/arifle_MX_Black_F");
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_reports_preprocessor_info() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "script_component.hpp", "#define COMPONENT medical\n#define MASS 10\n")?;
+        create_test_file(temp_dir.path(), "config.cpp", r#"
+            #include "script_component.hpp"
+            class CfgWeapons {
+                class Item {
+                    mass = MASS;
+                };
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            extensions: vec!["cpp".to_string()],
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config)?;
+        
+        let config_result = &result.results[&temp_dir.path().join("config.cpp")];
+        let info = config_result.preprocessor.as_ref().expect("preprocessor info should be recorded");
+        assert_eq!(info.includes.len(), 1);
+        assert_eq!(info.includes[0].resolved, Some(temp_dir.path().join("script_component.hpp")));
+        assert_eq!(info.definition("MASS").unwrap().line, 2);
+        assert_eq!(info.expansions_of("MASS").count(), 1);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_reports_structured_errors() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "missing_include.cpp", r#"
+            #include "missing.hpp"
+            class Test {};
+        "#)?;
+        create_test_file(temp_dir.path(), "bad_syntax.cpp", r#"
+            class Test {
+                value = ;
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            extensions: vec!["cpp".to_string()],
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config)?;
+        assert_eq!(result.failed_files, 2);
+        
+        let preprocess_errors = &result.errors[&temp_dir.path().join("missing_include.cpp")];
+        assert!(matches!(preprocess_errors[0], ScanError::Preprocess(_)));
+        assert!(preprocess_errors[0].diagnostic().code.is_some());
+        
+        let parse_errors = &result.errors[&temp_dir.path().join("bad_syntax.cpp")];
+        assert!(!parse_errors.is_empty());
+        assert!(parse_errors.iter().all(|e| matches!(e, ScanError::Parse(_))));
+        assert_eq!(parse_errors[0].file(), temp_dir.path().join("bad_syntax.cpp"));
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_keeps_warnings() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        // Redefining a macro without #undef is a preprocessor warning, not an error
+        create_test_file(temp_dir.path(), "config.cpp", r#"
+            #define MASS 10
+            #define MASS 20
+            class Item {
+                mass = MASS;
+            };
+        "#)?;
+        create_test_file(temp_dir.path(), "clean.cpp", r#"
+            class Other {
+                mass = 1;
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            extensions: vec!["cpp".to_string()],
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config)?;
+        assert_eq!(result.successful_files, 2);
+        
+        let config_result = &result.results[&temp_dir.path().join("config.cpp")];
+        assert_eq!(config_result.classes.len(), 1);
+        assert!(!config_result.diagnostics.is_empty());
+        let warning = &config_result.diagnostics[0];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert!(warning.code.is_some());
+        assert_eq!(warning.file, temp_dir.path().join("config.cpp"));
+        
+        assert!(result.results[&temp_dir.path().join("clean.cpp")].diagnostics.is_empty());
+        assert_eq!(result.files_with_warnings().count(), 1);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_recovers_partial_files() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "config.cpp", r#"
+            class CfgVehicles {
+                class Car { speed = 100; };
+                class Broken { speed = ; };
+            };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            extensions: vec!["cpp".to_string()],
+            recover_partial: true,
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config.clone())?;
+        assert_eq!(result.successful_files, 1);
+        
+        let partial = &result.results[&temp_dir.path().join("config.cpp")];
+        assert_eq!(partial.status, ParseStatus::Partial);
+        assert!(!partial.errors.is_empty());
+        assert!(partial.classes.iter().any(|c| c.name == "Car"));
+        assert!(!partial.classes.iter().any(|c| c.name == "Broken"));
+        assert_eq!(result.partial_results().count(), 1);
+        
+        // Without recovery, the default, the file fails as a whole
+        let result = scan_directory(temp_dir.path(), ScannerConfig { recover_partial: false, ..config })?;
+        assert!(!ScannerConfig::default().recover_partial);
+        assert_eq!(result.failed_files, 1);
+        assert!(result.results.is_empty());
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_selects_parser_by_name() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_file(temp_dir.path(), "config.cpp", r#"
+            #define SPEED 100
+            class Car { speed = SPEED; };
+        "#)?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            extensions: vec!["cpp".to_string()],
+            parser: "Simple".to_string(),
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config.clone())?;
+        
+        let file_result = &result.results[&temp_dir.path().join("config.cpp")];
+        assert!(file_result.preprocessor.is_none());
+        assert!(file_result.classes[0].is_low_fidelity());
+        assert!(file_result.classes[0].properties.is_empty());
+        
+        // A parser registered under the built-in name is used through the registry too
+        let mut registry = ParserRegistry::default();
+        registry.register_scanner(ADVANCED, ParserCapabilities::default(), Arc::new(parser_simple::scanner::SimpleScanner::new()));
+        let replaced = ScannerConfig { parser: ADVANCED.to_string(), registry, ..config.clone() };
+        let result = scan_directory(temp_dir.path(), replaced)?;
+        let file_result = &result.results[&temp_dir.path().join("config.cpp")];
+        assert!(file_result.preprocessor.is_none());
+        assert!(file_result.classes[0].is_low_fidelity());
+        
+        let error = scan_directory(temp_dir.path(), ScannerConfig { parser: "missing".to_string(), ..config }).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_reads_rapified_configs() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        
+        // `class Car {};` in a rapified config
+        let mut config = b"\0raP\0\0\0\0\x08\0\0\0\0\0\0\0".to_vec();
+        config.extend_from_slice(b"\0\x01\0Car\0");
+        config.extend_from_slice(&27u32.to_le_bytes());
+        config.extend_from_slice(b"\0\0");
+        fs::write(temp_dir.path().join("config.bin"), config)?;
+        fs::write(temp_dir.path().join("texHeaders.bin"), b"0DHT\x01\0\0\0")?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config)?;
+        assert_eq!(result.total_files, 1);
+        
+        let bin_result = &result.results[&temp_dir.path().join("config.bin")];
+        assert_eq!(bin_result.classes.len(), 1);
+        assert_eq!(bin_result.classes[0].name, "Car");
+        assert!(bin_result.preprocessor.is_none());
+        
+        Ok(())
+    }
+
+    /// A PBO with a prefix header and stored text entries
+    fn text_pbo(prefix: &str, entries: &[(&str, &str)]) -> Vec<u8> {
+        let entries: Vec<(&str, u32, u32, &[u8])> = entries.iter()
+            .map(|(name, content)| (*name, 0, 0, content.as_bytes()))
+            .collect();
+        build_pbo(prefix, &entries)
+    }
+
+    #[test]
+    fn test_scanner_reads_pbo_archives() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let addons_dir = temp_dir.path().join("@mod").join("addons");
+        fs::create_dir_all(&addons_dir)?;
+        let pbo_path = addons_dir.join("weapons.pbo");
+        fs::write(&pbo_path, text_pbo("x\\mod\\addons\\weapons", &[
+            ("config.cpp", r#"
+                #include "\x\mod\addons\weapons\script_component.hpp"
+                class CfgWeapons {
+                    class Rifle { speed = SPEED; };
+                };
+            "#),
+            ("script_component.hpp", "#define SPEED 100\n"),
+            ("data\\rifle.paa", "not a config"),
+        ]))?;
+        fs::write(addons_dir.join("broken.pbo"), b"\0\0")?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            scan_archives: true,
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config.clone())?;
+        assert_eq!(result.total_files, 3);
+        assert_eq!(result.successful_files, 2);
+        assert!(result.errors.contains_key(&addons_dir.join("broken.pbo")));
+        
+        let config_path = crate::archive_entry_path(&pbo_path, "config.cpp");
+        let config_result = &result.results[&config_path];
+        assert_eq!(config_result.addon.as_deref(), Some("x\\mod\\addons\\weapons"));
+        assert_eq!(config_result.virtual_path.as_deref(), Some("x\\mod\\addons\\weapons\\config.cpp"));
+        
+        let rifle = config_result.classes.iter().find(|c| c.name == "Rifle").unwrap();
+        assert_eq!(rifle.file_path, config_path);
+        assert!(matches!(rifle.properties[0].value, PropertyValue::Number(100)));
+        
+        // Archives are left alone unless asked for, e.g. when they were extracted beforehand
+        let result = scan_directory(temp_dir.path(), ScannerConfig { scan_archives: false, ..config })?;
+        assert_eq!(result.total_files, 0);
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_groups_results_by_mod() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mod_dir = temp_dir.path().join("@weapons_mod");
+        let addons_dir = mod_dir.join("addons");
+        fs::create_dir_all(&addons_dir)?;
+        create_test_file(&mod_dir, "mod.cpp", "name = \"Weapons Mod\";\n")?;
+        create_test_file(&mod_dir, "meta.cpp", "protocol = 1;\npublishedid = 123456789;\n")?;
+        fs::write(addons_dir.join("weapons.pbo"), text_pbo("x\\wm\\addons\\weapons", &[
+            ("config.cpp", "class CfgWeapons { class wm_rifle {}; };"),
+        ]))?;
+        
+        let loose_dir = temp_dir.path().join("loose");
+        fs::create_dir_all(&loose_dir)?;
+        create_test_file(&loose_dir, "config.cpp", "class CfgWeapons { class wm_rifle; class other_rifle {}; };")?;
+        
+        let config = ScannerConfig {
+            show_progress: false,
+            timeout: 30,
+            scan_archives: true,
+            mod_aware: true,
+            ..Default::default()
+        };
+        let result = scan_directory(temp_dir.path(), config.clone())?;
+        
+        // mod.cpp and meta.cpp are metadata, not configs
+        assert_eq!(result.total_files, 2);
+        assert_eq!(result.mods.mods().len(), 1);
+        
+        let info = &result.mods.mods()[0];
+        assert_eq!(info.name, "Weapons Mod");
+        assert_eq!(info.workshop_id, Some(123456789));
+        assert_eq!(info.addons, vec!["x\\wm\\addons\\weapons"]);
+        
+        let by_mod = result.results_by_mod();
+        assert_eq!(by_mod.len(), 1);
+        assert_eq!(by_mod[0].1.len(), 1);
+        
+        assert_eq!(result.mods_defining("WM_Rifle").len(), 1);
+        assert!(result.mods_defining("other_rifle").is_empty());
+        
+        // Mods are only recognised on request
+        let result = scan_directory(temp_dir.path(), ScannerConfig { mod_aware: false, ..config })?;
+        assert!(result.mods.is_empty());
+        assert_eq!(result.total_files, 4);
+        
+        Ok(())
+    }
+}
@@ -2,7 +2,9 @@ use std::sync::Arc;
 use gamedata_scanner_models::Scanner as ClassScanner;
 use gamedata_scanner_models::FileParser;
 
-/// Returns a scanner implementation based on the scanner type 
+/// Returns a scanner implementation based on the scanner type
+///
+/// Shortcut for the built-in parsers, see [`crate::ParserRegistry`] to select one by name
 pub fn get_scanner(advanced: bool) -> Arc<dyn ClassScanner> {
     if advanced {
         Arc::new(::parser_advanced::scanner::AdvancedScanner::new())
@@ -11,7 +13,15 @@ pub fn get_scanner(advanced: bool) -> Arc<dyn ClassScanner> {
     }
 }
 
+/// Returns a scanner that uses the advanced parser and falls back to the
+/// simple parser for files it can't preprocess or parse
+pub fn get_hybrid_scanner() -> Arc<dyn ClassScanner> {
+    Arc::new(crate::hybrid_scanner::HybridScanner::new())
+}
+
 /// Returns a file parser implementation based on the parser type
+///
+/// Shortcut for the built-in parsers, see [`crate::ParserRegistry`] to select one by name
 pub fn get_parser(advanced: bool) -> Arc<dyn FileParser> {
     if advanced {
         Arc::new(::parser_advanced::AdvancedFileParser::new())
@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
+
+/// File names (lowercase) that hold an addon's in-game path prefix
+const PREFIX_FILES: &[&str] = &["$pboprefix$", "$pboprefix$.txt", "pboprefix.txt"];
+
+/// An addon found while walking a directory tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddonInfo {
+    /// In-game path prefix, e.g. `z\ace\addons\medical`
+    pub prefix: String,
+    /// Addon directory on disk
+    pub path: PathBuf,
+    /// Whether the prefix was read from a `$PBOPREFIX$` file rather than
+    /// derived from the directory name
+    pub has_prefix_file: bool,
+}
+
+/// Map between in-game virtual paths (`\z\ace\addons\medical`) and directories on disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VirtualPathMap {
+    addons: Vec<AddonInfo>,
+}
+
+impl VirtualPathMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Walk `root` and record every addon it contains
+    pub fn discover(root: &Path) -> Self {
+        let mut map = Self::new();
+        map.discover_recursive(root);
+        map
+    }
+
+    fn discover_recursive(&mut self, dir: &Path) {
+        self.inspect_dir(dir);
+
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    self.discover_recursive(&path);
+                }
+            }
+        }
+    }
+
+    /// Check whether `dir` is an addon root and record it
+    ///
+    /// A directory is an addon root if it holds a `$PBOPREFIX$` (or `pboprefix.txt`)
+    /// file, or if it holds a `config.cpp` and is not already inside a known addon.
+    /// In the latter case the directory name is used as prefix, like a PBO without
+    /// a prefix header.
+    pub fn inspect_dir(&mut self, dir: &Path) -> Option<&AddonInfo> {
+        let entries = fs::read_dir(dir).ok()?;
+
+        let mut prefix_file = None;
+        let mut has_config = false;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            if PREFIX_FILES.contains(&name.as_str()) {
+                prefix_file = Some(entry.path());
+            } else if name == "config.cpp" {
+                has_config = true;
+            }
+        }
+
+        let addon = if let Some(prefix) = prefix_file.and_then(|file| read_prefix_file(&file)) {
+            AddonInfo {
+                prefix,
+                path: dir.to_path_buf(),
+                has_prefix_file: true,
+            }
+        } else if has_config && self.addon_for(dir).is_none() {
+            AddonInfo {
+                prefix: dir.file_name()?.to_string_lossy().to_string(),
+                path: dir.to_path_buf(),
+                has_prefix_file: false,
+            }
+        } else {
+            return None;
+        };
+
+        debug!("Found addon {} at {}", addon.prefix, addon.path.display());
+        self.insert(addon);
+        self.addons.last()
+    }
+
+    /// Add an addon to the map
+    pub fn insert(&mut self, addon: AddonInfo) {
+        self.addons.push(addon);
+    }
+
+    /// All known addons
+    pub fn addons(&self) -> &[AddonInfo] {
+        &self.addons
+    }
+
+    /// Whether no addons were found
+    pub fn is_empty(&self) -> bool {
+        self.addons.is_empty()
+    }
+
+    /// Find the addon that contains `path` (the innermost one if addons are nested)
+    pub fn addon_for(&self, path: &Path) -> Option<&AddonInfo> {
+        self.addons.iter()
+            .filter(|addon| path.starts_with(&addon.path))
+            .max_by_key(|addon| addon.path.components().count())
+    }
+
+    /// In-game path of a file or directory on disk, e.g. `z\ace\addons\medical\config.cpp`
+    pub fn virtual_path(&self, path: &Path) -> Option<String> {
+        let addon = self.addon_for(path)?;
+        let relative = path.strip_prefix(&addon.path).ok()?;
+
+        let mut virtual_path = addon.prefix.clone();
+        for component in relative.components() {
+            virtual_path.push('\\');
+            virtual_path.push_str(&component.as_os_str().to_string_lossy());
+        }
+        Some(virtual_path)
+    }
+
+    /// Directory or file on disk for an in-game path
+    ///
+    /// Matching is case-insensitive, like the game's file system.
+    pub fn resolve(&self, virtual_path: &str) -> Option<PathBuf> {
+        let wanted = split_virtual(virtual_path);
+
+        self.addons.iter()
+            .filter_map(|addon| {
+                let prefix = split_virtual(&addon.prefix);
+                let matches = prefix.len() <= wanted.len()
+                    && prefix.iter().zip(&wanted).all(|(a, b)| a.eq_ignore_ascii_case(b));
+                matches.then_some((prefix.len(), addon))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(len, addon)| {
+                wanted[len..].iter().fold(addon.path.clone(), |path, part| path.join(part))
+            })
+    }
+
+    /// `(prefix, directory)` pairs suitable for include resolution
+    ///
+    /// Only addons with a declared prefix (a `$PBOPREFIX$` file or PBO header)
+    /// are included: a prefix guessed from a directory name is rarely what
+    /// includes refer to, and mapping every `config.cpp` folder of a large tree
+    /// would only make include resolution slower.
+    pub fn prefix_mappings(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.addons.iter()
+            .filter(|addon| addon.has_prefix_file)
+            .map(|addon| (addon.prefix.as_str(), addon.path.as_path()))
+    }
+}
+
+/// Split an in-game path into its components
+fn split_virtual(path: &str) -> Vec<&str> {
+    path.split(['\\', '/'])
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Read the prefix from a `$PBOPREFIX$` file
+///
+/// Accepts both the plain form (`z\ace\addons\medical`) and the `prefix=` key
+/// used by some packing tools.
+fn read_prefix_file(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let value = match line.split_once('=') {
+            Some((key, value)) if key.trim().eq_ignore_ascii_case("prefix") => value,
+            Some(_) => continue,
+            None => line,
+        };
+
+        let prefix = value.trim().trim_matches(['\\', '/']).replace('/', "\\");
+        if !prefix.is_empty() {
+            trace!("Read prefix {} from {}", prefix, path.display());
+            return Some(prefix);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discover_addons() {
+        let temp_dir = TempDir::new().unwrap();
+        let medical = temp_dir.path().join("ace").join("addons").join("medical");
+        fs::create_dir_all(medical.join("ui")).unwrap();
+        fs::write(medical.join("$PBOPREFIX$"), "z\\ace\\addons\\medical\n").unwrap();
+        fs::write(medical.join("config.cpp"), "").unwrap();
+
+        let loose = temp_dir.path().join("my_addon");
+        fs::create_dir_all(&loose).unwrap();
+        fs::write(loose.join("config.cpp"), "").unwrap();
+        fs::write(loose.join("pboprefix.txt"), "prefix=x/my/addon\nversion=1\n").unwrap();
+
+        let bare = temp_dir.path().join("bare");
+        fs::create_dir_all(&bare).unwrap();
+        fs::write(bare.join("config.cpp"), "").unwrap();
+
+        let map = VirtualPathMap::discover(temp_dir.path());
+        assert_eq!(map.addons().len(), 3);
+
+        assert_eq!(
+            map.virtual_path(&medical.join("ui").join("RscTitles.hpp")).as_deref(),
+            Some("z\\ace\\addons\\medical\\ui\\RscTitles.hpp")
+        );
+        assert_eq!(map.virtual_path(&loose.join("config.cpp")).as_deref(), Some("x\\my\\addon\\config.cpp"));
+        assert_eq!(map.virtual_path(&bare.join("config.cpp")).as_deref(), Some("bare\\config.cpp"));
+        assert_eq!(map.virtual_path(temp_dir.path()), None);
+
+        assert_eq!(map.resolve("\\Z\\ACE\\addons\\medical\\ui"), Some(medical.join("ui")));
+        assert_eq!(map.resolve("\\a3\\data_f"), None);
+
+        let mut mapped: Vec<_> = map.prefix_mappings().map(|(prefix, _)| prefix).collect();
+        mapped.sort();
+        assert_eq!(mapped, ["x\\my\\addon", "z\\ace\\addons\\medical"]);
+    }
+}
@@ -1,37 +1,5 @@
-use clap::{Parser, ValueEnum};
+use clap::Parser;
 use std::path::PathBuf;
-use std::str::FromStr;
-use std::fmt;
-
-/// Parser type
-#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
-pub enum ParserType {
-    /// The simple regex-based parser
-    Simple,
-    /// The advanced parser with full property parsing
-    Advanced,
-}
-
-impl fmt::Display for ParserType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ParserType::Simple => write!(f, "simple"),
-            ParserType::Advanced => write!(f, "advanced"),
-        }
-    }
-}
-
-impl FromStr for ParserType {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "simple" => Ok(ParserType::Simple),
-            "advanced" => Ok(ParserType::Advanced),
-            _ => Err(format!("Unknown parser type: {}", s)),
-        }
-    }
-}
 
 /// Batch parser for Arma 3 game data files
 #[derive(Parser, Debug)]
@@ -78,7 +46,12 @@ pub struct Args {
     #[arg(long, default_value_t = true, hide = true)]
     pub use_advanced: bool,
     
-    /// Which parser to use (simple or advanced)
+    /// Name of the parser to use (built-in: simple, advanced or hybrid)
     #[arg(long, default_value = "advanced")]
-    pub parser_type: ParserType,
+    pub parser_type: String,
+    
+    /// Only run the preprocessor and write the expanded text of each file
+    /// to `<output_dir>/preprocessed`, annotated with `#line` origin comments
+    #[arg(long)]
+    pub preprocess_only: bool,
 } 
\ No newline at end of file
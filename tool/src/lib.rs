@@ -15,7 +15,8 @@ use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
 
 // Use the gamedata_scanner library as the entry point to scanner functionality
-use gamedata_scanner::{ClassScanner, get_scanner, GameClass};
+use gamedata_scanner::{ClassScanner, ClassKind, Diagnostic, Fidelity, GameClass, ParseOptions, ParserRegistry, ScanError, ScannerConfig, VirtualPathMap, include_options, parse_file_detailed, preprocess_file};
+use gamedata_scanner::parser_registry::ADVANCED;
 use gamedata_scanner_models::ScanResult;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,6 +29,10 @@ pub struct ScanSummary {
     pub files_failed: usize,
     pub classes_found: usize,
     pub parser_used: String,
+    #[serde(default)]
+    pub warnings_found: usize,
+    #[serde(default)]
+    pub files_with_warnings: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,27 +40,86 @@ pub struct ClassInfo {
     pub name: String,
     pub parent: Option<String>,
     pub file: String,
+    #[serde(default)]
+    pub fidelity: Fidelity,
+}
+
+/// An error or warning as written to the report
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiagnosticInfo {
+    /// File the location is in, which may be an include of the scanned file
+    pub file: String,
+    /// Error category (e.g. `Syntax Error`), or the severity of a warning
+    pub kind: String,
+    pub code: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl DiagnosticInfo {
+    fn new(kind: &str, diagnostic: &Diagnostic) -> Self {
+        let file = diagnostic.span.as_ref().map_or(&diagnostic.file, |span| &span.file);
+        Self {
+            file: file.to_string_lossy().to_string(),
+            kind: kind.to_string(),
+            code: diagnostic.code.clone(),
+            line: diagnostic.line(),
+            column: diagnostic.span.as_ref().map(|span| span.column),
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+impl From<&ScanError> for DiagnosticInfo {
+    fn from(error: &ScanError) -> Self {
+        Self::new(error.category(), error.diagnostic())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScanReport {
     pub summary: ScanSummary,
     pub classes: Vec<ClassInfo>,
+    #[serde(default)]
+    pub failures: Vec<DiagnosticInfo>,
+    #[serde(default)]
+    pub warnings: Vec<DiagnosticInfo>,
+}
+
+/// How files are parsed
+enum ParseMethod {
+    /// The built-in advanced parser, called directly for its diagnostics
+    Detailed(ParseOptions),
+    /// Any other parser, through its scanner
+    Scanner(Arc<dyn ClassScanner>),
+}
+
+/// Classes and warnings of a file that parsed
+struct FileOutcome {
+    classes: Vec<ClassInfo>,
+    warnings: Vec<DiagnosticInfo>,
 }
 
 pub fn run(args: config::Args) -> Result<(), String> {
-    // Set up logging
-    env_logger::Builder::new()
+    run_with_registry(args, &ParserRegistry::default())
+}
+
+/// Run the batch parser, looking up `--parser-type` in `registry`
+pub fn run_with_registry(args: config::Args, registry: &ParserRegistry) -> Result<(), String> {
+    // Set up logging, unless the caller already did
+    let _ = env_logger::Builder::new()
         .filter_level(log::LevelFilter::Info)
-        .init();
+        .try_init();
     
     let start_time = Local::now();
     
-    // Determine whether to use advanced parser from parser_type
-    let use_advanced = match args.parser_type {
-        config::ParserType::Advanced => true,
-        config::ParserType::Simple => false,
-    };
+    // Look up the parser by name
+    let entry = registry.get(&args.parser_type).ok_or_else(|| format!(
+        "Unknown parser type: {} (available: {})",
+        args.parser_type,
+        registry.names().collect::<Vec<_>>().join(", ")
+    ))?;
     
     info!("Starting batch parsing with {} parser", args.parser_type);
     info!("Input directory: {}", args.input_dir.display());
@@ -67,6 +131,25 @@ pub fn run(args: config::Args) -> Result<(), String> {
     let files = collect_files(&args.input_dir, &args.file_extensions, args.max_files)?;
     info!("Found {} files to process", files.len());
     
+    if args.preprocess_only {
+        return run_preprocess_only(&args, &files);
+    }
+    
+    // Like a scan, the built-in advanced parser is called directly so that its
+    // warnings can be reported, with includes resolved through addon prefixes
+    let _virtual_root;
+    let method = if entry.is_builtin() && entry.name.eq_ignore_ascii_case(ADVANCED) {
+        let addons = VirtualPathMap::discover(&args.input_dir);
+        let (options, virtual_root) = include_options(&args.input_dir, &ScannerConfig::default(), &addons)
+            .map_err(|e| format!("Failed to set up include resolution: {}", e))?;
+        _virtual_root = virtual_root;
+        ParseMethod::Detailed(options)
+    } else {
+        let scanner = entry.scanner.clone()
+            .ok_or_else(|| format!("Parser {} has no scanner", entry.name))?;
+        ParseMethod::Scanner(scanner)
+    };
+    
     let progress_bar = ProgressBar::new(files.len() as u64);
     progress_bar.set_style(
         ProgressStyle::default_bar()
@@ -78,122 +161,67 @@ pub fn run(args: config::Args) -> Result<(), String> {
     // Track progress and results
     let classes_found = Arc::new(Mutex::new(Vec::new()));
     let files_failed = Arc::new(Mutex::new(Vec::new()));
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let warnings = Arc::new(Mutex::new(Vec::new()));
     let files_processed = Arc::new(AtomicUsize::new(0));
     let fail_counter = Arc::new(AtomicUsize::new(0));
+    let warning_file_counter = Arc::new(AtomicUsize::new(0));
     
-    // Get the appropriate scanner from the main library
-    let scanner = get_scanner(use_advanced);
-    
-    // Process files
-    let result = if args.parallel {
-        // Create a list of file tasks with their own scanner instances
-        let result: Result<(), String> = files.par_iter().try_for_each(|file_path| {
-            // Create a fresh scanner for each thread
-            let thread_scanner = get_scanner(use_advanced);
-            
-            // Process the file
-            match process_single_file(file_path, &thread_scanner) {
-                Ok(file_classes) => {
-                    // Store class information
-                    let mut classes = classes_found.lock().unwrap();
-                    for class in file_classes {
-                        classes.push(ClassInfo {
-                            name: class.name,
-                            parent: class.parent,
-                            file: file_path.to_string_lossy().to_string(),
-                        });
-                    }
-                    
-                    let processed_count = files_processed.fetch_add(1, Ordering::SeqCst) + 1;
-                    if processed_count % 10 == 0 {
-                        progress_bar.set_message(format!("Found {} classes so far", classes.len()));
-                    }
-                },
-                Err(e) => {
-                    // Track failed files
-                    let mut failed = files_failed.lock().unwrap();
-                    failed.push(file_path.clone());
-                    
-                    // Copy failed file if requested
-                    if args.copy_failed_files {
-                        let file_name = file_path.file_name().unwrap_or_default();
-                        let dest_path = args.output_dir.join(file_name);
-                        if let Err(e) = fs::copy(file_path, &dest_path) {
-                            warn!("Failed to copy failed file: {}", e);
-                        }
-                    }
-                    
-                    let fail_count = fail_counter.fetch_add(1, Ordering::SeqCst) + 1;
-                    progress_bar.set_message(format!("Failed files: {}", fail_count));
-                    
-                    // Check if we've exceeded max failures
-                    if fail_count >= args.max_failures {
-                        warn!("Maximum failure count reached ({}), stopping scan", args.max_failures);
-                        return Err(format!("Maximum failure count reached ({})", args.max_failures));
-                    }
-
-                    // Increment files_processed counter for failed files too
-                    files_processed.fetch_add(1, Ordering::SeqCst);
+    let handle_file = |file_path: &PathBuf| -> Result<(), String> {
+        // Process the file
+        match process_single_file(file_path, &method) {
+            Ok(outcome) => {
+                if !outcome.warnings.is_empty() {
+                    warning_file_counter.fetch_add(1, Ordering::SeqCst);
+                    warnings.lock().unwrap().extend(outcome.warnings);
                 }
-            }
-            
-            progress_bar.inc(1);
-            Ok(())
-        });
-        
-        result
-    } else {
-        // Sequential processing
-        let result: Result<(), String> = files.iter().try_for_each(|file_path| {
-            // Process the file
-            match process_single_file(file_path, &scanner) {
-                Ok(file_classes) => {
-                    // Store class information
-                    let mut classes = classes_found.lock().unwrap();
-                    for class in file_classes {
-                        classes.push(ClassInfo {
-                            name: class.name,
-                            parent: class.parent,
-                            file: file_path.to_string_lossy().to_string(),
-                        });
-                    }
-                    
-                    let processed_count = files_processed.fetch_add(1, Ordering::SeqCst) + 1;
+                
+                // Store class information
+                let mut classes = classes_found.lock().unwrap();
+                classes.extend(outcome.classes);
+                
+                let processed_count = files_processed.fetch_add(1, Ordering::SeqCst) + 1;
+                if !args.parallel || processed_count % 10 == 0 {
                     progress_bar.set_message(format!("Found {} classes so far", classes.len()));
-                },
-                Err(e) => {
-                    // Track failed files
-                    let mut failed = files_failed.lock().unwrap();
-                    failed.push(file_path.clone());
-                    
-                    // Copy failed file if requested
-                    if args.copy_failed_files {
-                        let file_name = file_path.file_name().unwrap_or_default();
-                        let dest_path = args.output_dir.join(file_name);
-                        if let Err(e) = fs::copy(file_path, &dest_path) {
-                            warn!("Failed to copy failed file: {}", e);
-                        }
-                    }
-                    
-                    let fail_count = fail_counter.fetch_add(1, Ordering::SeqCst) + 1;
-                    progress_bar.set_message(format!("Failed files: {}", fail_count));
-                    
-                    // Check if we've exceeded max failures
-                    if fail_count >= args.max_failures {
-                        warn!("Maximum failure count reached ({}), stopping scan", args.max_failures);
-                        return Err(format!("Maximum failure count reached ({})", args.max_failures));
+                }
+            },
+            Err(errors) => {
+                // Track failed files
+                files_failed.lock().unwrap().push(file_path.clone());
+                failures.lock().unwrap().extend(errors.iter().map(DiagnosticInfo::from));
+                
+                // Copy failed file if requested
+                if args.copy_failed_files {
+                    let file_name = file_path.file_name().unwrap_or_default();
+                    let dest_path = args.output_dir.join(file_name);
+                    if let Err(e) = fs::copy(file_path, &dest_path) {
+                        warn!("Failed to copy failed file: {}", e);
                     }
-
-                    // Increment files_processed counter for failed files too
-                    files_processed.fetch_add(1, Ordering::SeqCst);
                 }
+                
+                let fail_count = fail_counter.fetch_add(1, Ordering::SeqCst) + 1;
+                progress_bar.set_message(format!("Failed files: {}", fail_count));
+                
+                // Check if we've exceeded max failures
+                if fail_count >= args.max_failures {
+                    warn!("Maximum failure count reached ({}), stopping scan", args.max_failures);
+                    return Err(format!("Maximum failure count reached ({})", args.max_failures));
+                }
+
+                // Increment files_processed counter for failed files too
+                files_processed.fetch_add(1, Ordering::SeqCst);
             }
-            
-            progress_bar.inc(1);
-            Ok(())
-        });
+        }
         
-        result
+        progress_bar.inc(1);
+        Ok(())
+    };
+    
+    // Process files
+    let result = if args.parallel {
+        files.par_iter().try_for_each(handle_file)
+    } else {
+        files.iter().try_for_each(handle_file)
     };
     
     progress_bar.finish_with_message("Scan complete");
@@ -204,6 +232,8 @@ pub fn run(args: config::Args) -> Result<(), String> {
     
     let all_classes = classes_found.lock().unwrap();
     let failed = files_failed.lock().unwrap();
+    let all_failures = failures.lock().unwrap();
+    let all_warnings = warnings.lock().unwrap();
     
     let summary = ScanSummary {
         start_time,
@@ -213,12 +243,16 @@ pub fn run(args: config::Args) -> Result<(), String> {
         files_processed: files_processed.load(Ordering::SeqCst),
         files_failed: failed.len(),
         classes_found: all_classes.len(),
-        parser_used: args.parser_type.to_string(),
+        parser_used: args.parser_type.clone(),
+        warnings_found: all_warnings.len(),
+        files_with_warnings: warning_file_counter.load(Ordering::SeqCst),
     };
     
     let report = ScanReport {
         summary: summary.clone(),
         classes: all_classes.clone(),
+        failures: all_failures.clone(),
+        warnings: all_warnings.clone(),
     };
     
     // Write report to JSON
@@ -236,6 +270,7 @@ pub fn run(args: config::Args) -> Result<(), String> {
     info!("  Parser used: {}", args.parser_type);
     info!("  Files processed: {}/{}", files_processed.load(Ordering::SeqCst), files.len());
     info!("  Files failed: {}", failed.len());
+    info!("  Warnings: {} in {} files", all_warnings.len(), summary.files_with_warnings);
     info!("  Classes found: {}", all_classes.len());
     info!("  Time taken: {:.2} seconds", summary.duration_seconds);
     info!("  Report saved to: {}", args.report_path.display());
@@ -247,6 +282,58 @@ pub fn run(args: config::Args) -> Result<(), String> {
     }
 }
 
+/// Write the preprocessed text of every file instead of parsing it
+/// 
+/// Output mirrors the input tree under `<output_dir>/preprocessed`, with `.i`
+/// appended to each file name. Files that fail to preprocess get a `.error.txt`
+/// with the preprocessor errors instead. Includes resolve as in a scan of
+/// the input directory, through the prefixes of the addons found in it.
+fn run_preprocess_only(args: &config::Args, files: &[PathBuf]) -> Result<(), String> {
+    let output_root = args.output_dir.join("preprocessed");
+    let addons = VirtualPathMap::discover(&args.input_dir);
+    let (options, _virtual_root) = include_options(&args.input_dir, &ScannerConfig::default(), &addons)
+        .map_err(|e| format!("Failed to set up include resolution: {}", e))?;
+    let failures = AtomicUsize::new(0);
+    
+    let write_output = |file_path: &PathBuf| -> Result<(), String> {
+        let relative = file_path.strip_prefix(&args.input_dir).unwrap_or(file_path);
+        let mut output_path = output_root.join(relative).into_os_string();
+        
+        let content = match preprocess_file(file_path, &options) {
+            Ok(output) => {
+                output_path.push(".i");
+                output.annotated()
+            },
+            Err(errors) => {
+                warn!("Failed to preprocess {}", file_path.display());
+                failures.fetch_add(1, Ordering::SeqCst);
+                output_path.push(".error.txt");
+                errors.iter().map(|e| format!("{}\n", e)).collect()
+            }
+        };
+        
+        let output_path = PathBuf::from(output_path);
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+        fs::write(&output_path, content)
+            .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))
+    };
+    
+    if args.parallel {
+        files.par_iter().try_for_each(write_output)?;
+    } else {
+        files.iter().try_for_each(write_output)?;
+    }
+    
+    info!("Preprocessing complete:");
+    info!("  Files preprocessed: {}", files.len() - failures.load(Ordering::SeqCst));
+    info!("  Files failed: {}", failures.load(Ordering::SeqCst));
+    info!("  Output written to: {}", output_root.display());
+    Ok(())
+}
+
 fn collect_files(dir_path: &Path, extensions: &str, max_files: usize) -> Result<Vec<PathBuf>, String> {
     let mut files = Vec::new();
     let exts: Vec<&str> = extensions.split(',').collect();
@@ -284,29 +371,37 @@ fn collect_files_recursive(dir_path: &Path, files: &mut Vec<PathBuf>, extensions
     Ok(())
 }
 
-/// Process a single file using the scanner from the main library
-fn process_single_file(file_path: &Path, scanner: &Arc<dyn ClassScanner>) -> Result<Vec<ClassInfo>, String> {
+/// Process a single file with the selected parser
+fn process_single_file(file_path: &Path, method: &ParseMethod) -> Result<FileOutcome, Vec<ScanError>> {
     debug!("Processing file: {}", file_path.display());
     
     let start = Instant::now();
     
-    // Use the scanner to parse the file
-    match scanner.parse_file(file_path) {
-        Ok(parsed_classes) => {
-            let classes = parsed_classes.into_iter()
-                .map(|class| ClassInfo {
-                    name: class.name,
-                    parent: class.parent,
-                    file: file_path.to_string_lossy().to_string(),
-                })
-                .collect::<Vec<ClassInfo>>();
-            
-            let duration = start.elapsed();
-            debug!("Processed {} in {:.2?}, found {} classes", 
-                   file_path.display(), duration, classes.len());
-            
-            Ok(classes)
+    let (parsed_classes, diagnostics) = match method {
+        ParseMethod::Detailed(options) => {
+            let parsed = parse_file_detailed(file_path, options)?;
+            (parsed.classes, parsed.diagnostics)
         },
-        Err(e) => Err(format!("Failed to parse file {}: {}", file_path.display(), e))
-    }
-} 
\ No newline at end of file
+        ParseMethod::Scanner(scanner) => (scanner.parse_file(file_path)?, Vec::new()),
+    };
+    
+    // Deletions remove a class rather than declare one
+    let classes = parsed_classes.into_iter()
+        .filter(|class| class.kind != ClassKind::Deleted)
+        .map(|class| ClassInfo {
+            name: class.name,
+            parent: class.parent,
+            file: file_path.to_string_lossy().to_string(),
+            fidelity: class.fidelity,
+        })
+        .collect::<Vec<ClassInfo>>();
+    let warnings = diagnostics.iter()
+        .map(|diagnostic| DiagnosticInfo::new(&diagnostic.severity.to_string(), diagnostic))
+        .collect();
+    
+    let duration = start.elapsed();
+    debug!("Processed {} in {:.2?}, found {} classes", 
+           file_path.display(), duration, classes.len());
+    
+    Ok(FileOutcome { classes, warnings })
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use batch_scanner::config::Args;
+    use batch_scanner::{run_with_registry, ScanReport};
+    use gamedata_scanner::ParserRegistry;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn args(input_dir: &Path, output_dir: &Path) -> Args {
+        Args {
+            input_dir: input_dir.to_path_buf(),
+            output_dir: output_dir.join("failed"),
+            report_path: output_dir.join("report.json"),
+            diagnostic_path: output_dir.join("diagnostics"),
+            file_extensions: "cpp".to_string(),
+            max_files: 100,
+            max_failures: 10,
+            copy_failed_files: false,
+            parallel: false,
+            use_advanced: true,
+            parser_type: "advanced".to_string(),
+            preprocess_only: false,
+        }
+    }
+
+    #[test]
+    fn test_report_counts_warnings_and_failures() {
+        let input = TempDir::new().unwrap();
+        let output = TempDir::new().unwrap();
+        // Redefining a macro without #undef is a preprocessor warning, not an error
+        fs::write(input.path().join("warned.cpp"), "#define MASS 10\n#define MASS 20\nclass Item { mass = MASS; };\n").unwrap();
+        fs::write(input.path().join("clean.cpp"), "class Other { mass = 1; };\n").unwrap();
+        fs::write(input.path().join("broken.cpp"), "class Broken {\n    value = ;\n};\n").unwrap();
+
+        let args = args(input.path(), output.path());
+        run_with_registry(args, &ParserRegistry::default()).unwrap();
+
+        let json = fs::read_to_string(output.path().join("report.json")).unwrap();
+        let report: ScanReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(report.summary.files_processed, 3);
+        assert_eq!(report.summary.files_failed, 1);
+        assert_eq!(report.summary.files_with_warnings, 1);
+        assert!(report.summary.warnings_found >= 1);
+        assert_eq!(report.warnings.len(), report.summary.warnings_found);
+        assert!(report.warnings.iter().all(|warning| warning.kind == "Warning"
+            && Path::new(&warning.file) == input.path().join("warned.cpp")));
+
+        assert!(!report.failures.is_empty());
+        let failure = &report.failures[0];
+        assert_eq!(failure.kind, "Syntax Error");
+        assert_eq!(Path::new(&failure.file), input.path().join("broken.cpp"));
+        assert_eq!(failure.line, Some(2));
+    }
+
+    #[test]
+    fn test_report_without_warnings_from_other_parsers() {
+        let input = TempDir::new().unwrap();
+        let output = TempDir::new().unwrap();
+        fs::write(input.path().join("warned.cpp"), "#define MASS 10\n#define MASS 20\nclass Item { mass = MASS; };\n").unwrap();
+
+        let args = Args { parser_type: "simple".to_string(), ..args(input.path(), output.path()) };
+        run_with_registry(args, &ParserRegistry::default()).unwrap();
+
+        let json = fs::read_to_string(output.path().join("report.json")).unwrap();
+        let report: ScanReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(report.summary.warnings_found, 0);
+        assert_eq!(report.summary.files_with_warnings, 0);
+        assert!(report.warnings.is_empty());
+    }
+}